@@ -0,0 +1,83 @@
+use crate::HeightMapView;
+
+use ndshape::Shape;
+
+/// Parameters for the per-vertex ambient occlusion term. See
+/// [`HeightMeshConfig::ambient_occlusion`](crate::HeightMeshConfig::ambient_occlusion).
+#[derive(Clone, Copy, Debug)]
+pub struct AmbientOcclusionConfig {
+    /// How many samples to march along each direction while searching for the horizon.
+    pub radius: u32,
+    /// Scales the occlusion before it is subtracted from 1. A value of 1 is physically plausible; larger values exaggerate
+    /// creases.
+    pub strength: f32,
+}
+
+impl Default for AmbientOcclusionConfig {
+    fn default() -> Self {
+        Self {
+            radius: 8,
+            strength: 1.0,
+        }
+    }
+}
+
+const DIRECTIONS: [[i32; 2]; 8] = [
+    [1, 0],
+    [1, 1],
+    [0, 1],
+    [-1, 1],
+    [-1, 0],
+    [-1, -1],
+    [0, -1],
+    [1, -1],
+];
+
+/// Writes an ambient occlusion term in `[0, 1]` (1 is fully unoccluded) for each point in the row-major grid `[imin, imax]`.
+///
+/// For each of 8 directions, the horizon is found by marching up to `radius` samples (staying inside `[min, max]`) and
+/// keeping the steepest elevation angle. The occlusion of a direction is the sine of its horizon angle, and the final term is
+/// one minus the average over all directions.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn ambient_occlusion<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    imin: [u32; 2],
+    imax: [u32; 2],
+    vertical_scale: f32,
+    config: &AmbientOcclusionConfig,
+    ao: &mut Vec<f32>,
+) {
+    let min = [min[0] as i32, min[1] as i32];
+    let max = [max[0] as i32, max[1] as i32];
+
+    for z in imin[1]..=imax[1] {
+        for x in imin[0]..=imax[0] {
+            let h = height_map.height(map_shape.linearize([x, z]) as usize);
+
+            let mut occlusion = 0.0;
+            for [dx, dz] in DIRECTIONS {
+                let step_length = ((dx * dx + dz * dz) as f32).sqrt();
+                let mut max_slope = 0.0f32;
+                for k in 1..=config.radius as i32 {
+                    let px = x as i32 + k * dx;
+                    let pz = z as i32 + k * dz;
+                    if px < min[0] || px > max[0] || pz < min[1] || pz > max[1] {
+                        break;
+                    }
+                    let sample =
+                        height_map.height(map_shape.linearize([px as u32, pz as u32]) as usize);
+                    max_slope =
+                        max_slope.max(vertical_scale * (sample - h) / (k as f32 * step_length));
+                }
+                // sin(atan(slope))
+                occlusion += max_slope / (1.0 + max_slope * max_slope).sqrt();
+            }
+            occlusion /= DIRECTIONS.len() as f32;
+
+            ao.push((1.0 - config.strength * occlusion).max(0.0));
+        }
+    }
+}
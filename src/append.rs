@@ -0,0 +1,246 @@
+use crate::{normals, HeightMeshBuffer};
+
+use std::collections::HashMap;
+
+/// The boundary vertices of a mesh assembled by [`HeightMeshBuffer::append`], keyed by position.
+///
+/// This describes the mesh as of the last append. If the number of vertices has changed since then, the next append
+/// rebuilds it from [`HeightMeshBuffer::boundary_loops`].
+#[derive(Clone, Debug, Default)]
+pub struct SeamIndex {
+    vertices: HashMap<[u32; 3], u32>,
+    num_vertices: usize,
+}
+
+impl SeamIndex {
+    /// Clears the index, but keeps the memory allocated for reuse.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.num_vertices = 0;
+    }
+}
+
+impl HeightMeshBuffer {
+    /// Appends `other`, translated by `offset`, to this mesh, welding the seam between them.
+    ///
+    /// Any boundary vertex of `other` that lands exactly on a boundary vertex of `self` is replaced by the existing vertex
+    /// instead of being duplicated, so adjacent chunks merge into one watertight mesh with fewer vertices. Chunks meshed from
+    /// height maps that share their border samples weld perfectly.
+    ///
+    /// The positions and indices are always appended. Normals are appended if both meshes have them, in the format of
+    /// `self`; if `other` stores them in the other format ([`HeightMeshBuffer::normals`] or
+    /// [`HeightMeshBuffer::packed_normals`]), they are converted. Each other optional channel (UVs, colors, lightmap
+    /// UVs, curvature, ambient occlusion, texture layers, line indices, LOD indices, slope partitions, and triangle
+    /// materials) is appended only if both meshes have it, and otherwise cleared, so that the channels never fall out of
+    /// alignment. Welded vertices keep the attributes from `self`.
+    /// [`HeightMeshBuffer::stride_to_index`] and the shadow caster describe a single chunk, so they are left untouched.
+    /// [`HeightMeshBuffer::triangle_cells`] refer to each chunk's own height map, and [`HeightMeshBuffer::content_hash`] to
+    /// a single chunk's inputs, so they are cleared.
+    ///
+    /// Positions are compared exactly, except that `-0.0` and `0.0` are the same. The boundary vertices of the appended
+    /// chunks are kept in [`HeightMeshBuffer::seam_index`], so each call only costs time proportional to the size of `other`.
+    pub fn append(&mut self, other: &HeightMeshBuffer, offset: [f32; 3]) {
+        // An empty mesh has no channels yet, so it adopts whatever `other` has.
+        let was_empty = self.positions.is_empty();
+        // Normals keep the format of `self` (or of `other` if `self` is empty), converting the other side's normals.
+        let keep_normals = self.has_normals() && other.has_normals();
+        let packed_normals = if was_empty {
+            other.normals.len() != other.positions.len()
+        } else {
+            self.normals.len() != self.positions.len()
+        };
+        let translate = |[x, y, z]: [f32; 3]| [x + offset[0], y + offset[1], z + offset[2]];
+
+        // Only the boundary vertices of each appended chunk are indexed, so appending doesn't rescan the whole mesh. The
+        // index is rebuilt if the mesh was changed some other way since the last append.
+        let mut seam = std::mem::take(&mut self.seam_index);
+        if seam.num_vertices != self.positions.len() {
+            seam.vertices.clear();
+            for boundary_loop in self.boundary_loops() {
+                for v in boundary_loop {
+                    seam.vertices
+                        .insert(weld_key(self.positions[v as usize]), v);
+                }
+            }
+        }
+
+        // Map every vertex of `other` to its index in `self`.
+        let mut other_is_boundary = vec![false; other.positions.len()];
+        for boundary_loop in other.boundary_loops() {
+            for v in boundary_loop {
+                other_is_boundary[v as usize] = true;
+            }
+        }
+        let mut remap = Vec::with_capacity(other.positions.len());
+        let mut new_vertices = Vec::new();
+        for (v, &p) in other.positions.iter().enumerate() {
+            let p = translate(p);
+            let welded = if other_is_boundary[v] {
+                let key = weld_key(p);
+                // Positions edited in place since they were indexed no longer match their key.
+                seam.vertices.get(&key).copied().filter(|&w| {
+                    self.positions
+                        .get(w as usize)
+                        .is_some_and(|&q| weld_key(q) == key)
+                })
+            } else {
+                None
+            };
+            remap.push(welded.unwrap_or_else(|| {
+                let index = self.positions.len() as u32;
+                self.positions.push(p);
+                new_vertices.push(v);
+                index
+            }));
+            if other_is_boundary[v] {
+                seam.vertices.entry(weld_key(p)).or_insert(remap[v]);
+            }
+        }
+        seam.num_vertices = self.positions.len();
+        self.seam_index = seam;
+        // The hash described the inputs of a single chunk.
+        self.content_hash = 0;
+
+        if !keep_normals {
+            self.normals.clear();
+            self.packed_normals.clear();
+        } else if packed_normals {
+            self.normals.clear();
+            self.packed_normals.extend(new_vertices.iter().map(|&v| {
+                match other.packed_normals.get(v) {
+                    Some(&n) => n,
+                    None => normals::pack(other.normals[v]),
+                }
+            }));
+        } else {
+            self.packed_normals.clear();
+            self.normals
+                .extend(new_vertices.iter().map(|&v| other.normal(v).unwrap()));
+        }
+        append_channel(&mut self.uvs, &other.uvs, &new_vertices, was_empty);
+        append_channel(&mut self.colors, &other.colors, &new_vertices, was_empty);
+        append_channel(
+            &mut self.lightmap_uvs,
+            &other.lightmap_uvs,
+            &new_vertices,
+            was_empty,
+        );
+        append_channel(
+            &mut self.curvature,
+            &other.curvature,
+            &new_vertices,
+            was_empty,
+        );
+        append_channel(
+            &mut self.ambient_occlusion,
+            &other.ambient_occlusion,
+            &new_vertices,
+            was_empty,
+        );
+        append_channel(
+            &mut self.texture_layers,
+            &other.texture_layers,
+            &new_vertices,
+            was_empty,
+        );
+        append_channel(
+            &mut self.texture_layer_weights,
+            &other.texture_layer_weights,
+            &new_vertices,
+            was_empty,
+        );
+
+        let had_materials = self.triangle_materials.len() * 3 == self.indices.len();
+        if had_materials && other.triangle_materials.len() * 3 == other.indices.len() {
+            self.triangle_materials
+                .extend_from_slice(&other.triangle_materials);
+        } else {
+            self.triangle_materials.clear();
+        }
+        // Cell strides refer to each mesh's own height map, so they mean nothing once meshes are combined.
+        self.triangle_cells.clear();
+
+        self.indices
+            .extend(other.indices.iter().map(|&i| remap[i as usize]));
+        if (was_empty || !self.line_indices.is_empty()) && !other.line_indices.is_empty() {
+            self.line_indices
+                .extend(other.line_indices.iter().map(|&i| remap[i as usize]));
+        } else {
+            self.line_indices.clear();
+        }
+        let had_partition = !self.walkable_indices.is_empty() || !self.steep_indices.is_empty();
+        let has_partition = !other.walkable_indices.is_empty() || !other.steep_indices.is_empty();
+        if (was_empty || had_partition) && has_partition {
+            self.walkable_indices
+                .extend(other.walkable_indices.iter().map(|&i| remap[i as usize]));
+            self.steep_indices
+                .extend(other.steep_indices.iter().map(|&i| remap[i as usize]));
+        } else {
+            self.walkable_indices.clear();
+            self.steep_indices.clear();
+        }
+        if (was_empty || self.lod_indices.len() == other.lod_indices.len())
+            && !other.lod_indices.is_empty()
+        {
+            self.lod_indices
+                .resize_with(other.lod_indices.len(), Vec::new);
+            for (dst, src) in self.lod_indices.iter_mut().zip(other.lod_indices.iter()) {
+                dst.extend(src.iter().map(|&i| remap[i as usize]));
+            }
+        } else {
+            self.lod_indices.clear();
+        }
+    }
+}
+
+/// The exact bits of `p`, except that `-0.0` and `0.0` get the same key.
+fn weld_key(p: [f32; 3]) -> [u32; 3] {
+    p.map(|c| if c == 0.0 { 0 } else { c.to_bits() })
+}
+
+fn append_channel<T: Copy>(dst: &mut Vec<T>, src: &[T], new_vertices: &[usize], was_empty: bool) {
+    if (dst.is_empty() && !was_empty) || src.is_empty() {
+        dst.clear();
+        return;
+    }
+    dst.extend(new_vertices.iter().map(|&v| src[v]));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::height_mesh;
+    use ndshape::{ConstShape, ConstShape2u32};
+
+    #[test]
+    fn appended_chunks_weld_their_shared_borders() {
+        type MapShape = ConstShape2u32<12, 6>;
+        let heights = [0.0; MapShape::SIZE as usize];
+
+        // Three chunks in a row, each sharing a column of vertices with the next. The middle chunk's Y coordinates are -0.0.
+        let mut mesh = HeightMeshBuffer::default();
+        for min in [0, 3, 6] {
+            let mut chunk = HeightMeshBuffer::default();
+            height_mesh(&heights, &MapShape {}, [min, 0], [min + 5, 5], &mut chunk);
+            if min == 3 {
+                for p in &mut chunk.positions {
+                    p[1] = -0.0;
+                }
+            }
+            // A zero offset would turn -0.0 into 0.0, so offset by -0.0 to keep them apart.
+            mesh.append(&chunk, [0.0, -0.0, 0.0]);
+        }
+
+        // 10 columns by 4 rows of vertices, with no duplicates.
+        assert_eq!(mesh.positions.len(), 40);
+        let mut keys: Vec<_> = mesh.positions.iter().map(|&p| weld_key(p)).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), 40);
+
+        let loops = mesh.boundary_loops();
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].len(), 2 * (10 + 4) - 4);
+    }
+}
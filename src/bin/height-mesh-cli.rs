@@ -0,0 +1,424 @@
+//! Converts height map images and DEMs to meshes.
+//!
+//! ```text
+//! height-mesh-cli <input> <output> [options]
+//! ```
+//!
+//! Inputs are chosen by extension: binary PGM (`.pgm`, 8 or 16 bits), ESRI ASCII grid DEMs (`.asc`), or RAW16 (`.raw`,
+//! `.r16`) with a sidecar written by `height_mesh::raw16`. Outputs are OBJ (`.obj`), binary STL (`.stl`), glTF
+//! (`.gltf`), or PLY (`.ply`) and XYZ (`.xyz`) point clouds.
+
+use height_mesh::ndshape::Shape2u32;
+use height_mesh::raw16::{read_raw16, ByteOrder, Raw16Metadata};
+use height_mesh::{
+    downsample, export, height_mesh_with_config, DownsamplePolicy, HeightMeshBuffer,
+    HeightMeshConfig, SpatialMetadata,
+};
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: height-mesh-cli <input> <output> [options]
+
+Inputs:  .pgm (binary, 8 or 16 bit), .asc (ESRI ASCII grid), .raw/.r16 (RAW16)
+Outputs: .obj, .stl, .gltf, .ply and .xyz (point clouds)
+
+Options:
+  --scale <s>               Horizontal distance between samples [default: 1, or the DEM cell size]
+  --vertical-scale <s>      Multiplies every height [default: 1]
+  --lod <n>                 Downsample the map by 2^n before meshing [default: 0]
+  --lod-policy <p>          How --lod combines samples: average, max, or min [default: average]
+  --region <x0,y0,x1,y1>    Only mesh samples in this inclusive rectangle (before downsampling)
+  --raw-size <w,h>          RAW16 dimensions, if there is no sidecar
+  --raw-range <min,max>     RAW16 height range, if there is no sidecar [default: 0,1]
+  --raw-big-endian          RAW16 samples are big-endian, if there is no sidecar
+  --raw-sidecar <path>      RAW16 sidecar [default: <input>.meta]
+";
+
+struct Args {
+    input: PathBuf,
+    output: PathBuf,
+    scale: Option<f32>,
+    vertical_scale: f32,
+    lod: u32,
+    lod_policy: DownsamplePolicy,
+    region: Option<[u32; 4]>,
+    raw_size: Option<[u32; 2]>,
+    raw_range: [f32; 2],
+    raw_big_endian: bool,
+    raw_sidecar: Option<PathBuf>,
+}
+
+struct HeightMap {
+    size: [u32; 2],
+    heights: Vec<f32>,
+    /// The distance between samples, if the format records it.
+    spacing: Option<f32>,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args) -> io::Result<()> {
+    let mut map = read_input(args)?;
+
+    if let Some([x0, y0, x1, y1]) = args.region {
+        map = crop(&map, [x0, y0], [x1, y1])?;
+    }
+    for _ in 0..args.lod {
+        let (heights, size) = downsample(&map.heights, map.size, args.lod_policy);
+        map = HeightMap {
+            size,
+            heights,
+            spacing: map.spacing,
+        };
+    }
+    if map.size[0] < 3 || map.size[1] < 3 {
+        return Err(invalid_input(
+            "the map must be at least 3x3 samples to mesh",
+        ));
+    }
+
+    let spacing = args.scale.or(map.spacing).unwrap_or(1.0) * (1 << args.lod) as f32;
+
+    let shape = Shape2u32::new(map.size);
+    let config = HeightMeshConfig {
+        vertical_scale: args.vertical_scale,
+        ..Default::default()
+    };
+    let mut buffer = HeightMeshBuffer::default();
+    height_mesh_with_config(
+        &map.heights,
+        &shape,
+        [0; 2],
+        [map.size[0] - 1, map.size[1] - 1],
+        &config,
+        &mut buffer,
+    );
+
+    // Apply the horizontal spacing. The normals come from gradients per sample, so they scale inversely.
+    for p in buffer.positions.iter_mut() {
+        p[0] *= spacing;
+        p[2] *= spacing;
+    }
+    for n in buffer.normals.iter_mut() {
+        n[0] /= spacing;
+        n[2] /= spacing;
+    }
+    buffer.metadata = Some(SpatialMetadata {
+        horizontal_spacing: [spacing as f64; 2],
+        ..Default::default()
+    });
+
+    let writer = BufWriter::new(File::create(&args.output)?);
+    match extension(&args.output).as_str() {
+        "obj" => export::write_obj(&buffer, writer),
+        "stl" => export::write_stl(&buffer, writer),
+        "gltf" => export::write_gltf(&buffer, writer),
+        "ply" => export::write_ply_points(&buffer, writer),
+        "xyz" => export::write_xyz(&buffer, writer),
+        other => Err(invalid_input(&format!(
+            "unsupported output format {:?}",
+            other
+        ))),
+    }
+}
+
+fn read_input(args: &Args) -> io::Result<HeightMap> {
+    match extension(&args.input).as_str() {
+        "pgm" => read_pgm(BufReader::new(File::open(&args.input)?)),
+        "asc" => read_esri_ascii(BufReader::new(File::open(&args.input)?)),
+        "raw" | "r16" => {
+            let sidecar = args.raw_sidecar.clone().unwrap_or_else(|| {
+                let mut path = args.input.clone().into_os_string();
+                path.push(".meta");
+                path.into()
+            });
+            let metadata = if let Some(size) = args.raw_size {
+                Raw16Metadata {
+                    size,
+                    min_height: args.raw_range[0],
+                    max_height: args.raw_range[1],
+                    byte_order: if args.raw_big_endian {
+                        ByteOrder::Big
+                    } else {
+                        ByteOrder::Little
+                    },
+                }
+            } else if sidecar.exists() {
+                Raw16Metadata::read_sidecar(BufReader::new(File::open(&sidecar)?))?
+            } else {
+                return Err(invalid_input("RAW16 input needs --raw-size or a sidecar"));
+            };
+            let heights = read_raw16(BufReader::new(File::open(&args.input)?), &metadata)?;
+            Ok(HeightMap {
+                size: metadata.size,
+                heights,
+                spacing: None,
+            })
+        }
+        other => Err(invalid_input(&format!(
+            "unsupported input format {:?}",
+            other
+        ))),
+    }
+}
+
+/// Reads a binary (P5) PGM. Image rows go from top to bottom, so they are flipped to make +Z point up the image.
+fn read_pgm<R: BufRead>(mut reader: R) -> io::Result<HeightMap> {
+    if pgm_token(&mut reader)? != "P5" {
+        return Err(invalid_input("only binary (P5) PGM files are supported"));
+    }
+    let width: u32 = parse(&pgm_token(&mut reader)?)?;
+    let height: u32 = parse(&pgm_token(&mut reader)?)?;
+    let max_value: u32 = parse(&pgm_token(&mut reader)?)?;
+    if !(1..=65535).contains(&max_value) {
+        return Err(invalid_input(
+            "PGM maximum value must be between 1 and 65535",
+        ));
+    }
+
+    let num_samples = width as usize * height as usize;
+    let bytes_per_sample = if max_value < 256 { 1 } else { 2 };
+    let mut bytes = vec![0; bytes_per_sample * num_samples];
+    reader.read_exact(&mut bytes)?;
+
+    let mut heights = vec![0.0; num_samples];
+    for (i, sample) in bytes.chunks_exact(bytes_per_sample).enumerate() {
+        let value = match sample {
+            [v] => *v as f32,
+            // 16-bit PGM is big-endian.
+            [hi, lo] => u16::from_be_bytes([*hi, *lo]) as f32,
+            _ => unreachable!(),
+        };
+        let (x, row) = (i % width as usize, i / width as usize);
+        let y = height as usize - 1 - row;
+        heights[y * width as usize + x] = value / max_value as f32;
+    }
+
+    Ok(HeightMap {
+        size: [width, height],
+        heights,
+        spacing: None,
+    })
+}
+
+/// Reads the next whitespace-separated PGM header token, skipping `#` comments. This consumes the single whitespace byte
+/// after the token, so after the last header token the reader is at the start of the samples.
+fn pgm_token<R: BufRead>(reader: &mut R) -> io::Result<String> {
+    let mut token = Vec::new();
+    let mut byte = [0];
+    loop {
+        if reader.read(&mut byte)? == 0 {
+            if token.is_empty() {
+                return Err(invalid_input("truncated PGM header"));
+            }
+            break;
+        }
+        match byte[0] {
+            b'#' => {
+                reader.read_until(b'\n', &mut Vec::new())?;
+                if !token.is_empty() {
+                    break;
+                }
+            }
+            b if b.is_ascii_whitespace() => {
+                if !token.is_empty() {
+                    break;
+                }
+            }
+            b => token.push(b),
+        }
+    }
+    String::from_utf8(token).map_err(|_| invalid_input("invalid PGM header"))
+}
+
+/// Reads an ESRI ASCII grid. Rows go from north to south, so they are flipped to make +Z point north. No-data cells are
+/// set to the lowest valid height.
+fn read_esri_ascii<R: BufRead>(reader: R) -> io::Result<HeightMap> {
+    let mut ncols = None;
+    let mut nrows = None;
+    let mut cellsize = None;
+    let mut nodata = None;
+    let mut values = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let mut tokens = line.split_whitespace().peekable();
+        let Some(&first) = tokens.peek() else {
+            continue;
+        };
+        if first.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            let key = first.to_ascii_lowercase();
+            let value = line.split_whitespace().nth(1).unwrap_or("");
+            match key.as_str() {
+                "ncols" => ncols = Some(parse::<u32>(value)?),
+                "nrows" => nrows = Some(parse::<u32>(value)?),
+                "cellsize" => cellsize = Some(parse::<f32>(value)?),
+                "nodata_value" => nodata = Some(parse::<f32>(value)?),
+                _ => {}
+            }
+        } else {
+            for token in tokens {
+                values.push(parse::<f32>(token)?);
+            }
+        }
+    }
+
+    let (Some(width), Some(height)) = (ncols, nrows) else {
+        return Err(invalid_input("ASCII grid is missing ncols or nrows"));
+    };
+    let num_samples = width as usize * height as usize;
+    if values.len() != num_samples {
+        return Err(invalid_input("ASCII grid has the wrong number of values"));
+    }
+
+    let is_valid = |v: f32| nodata != Some(v);
+    let fill = values
+        .iter()
+        .copied()
+        .filter(|&v| is_valid(v))
+        .fold(f32::INFINITY, f32::min);
+    let fill = if fill.is_finite() { fill } else { 0.0 };
+
+    let mut heights = vec![0.0; num_samples];
+    for (i, &v) in values.iter().enumerate() {
+        let (x, row) = (i % width as usize, i / width as usize);
+        let y = height as usize - 1 - row;
+        heights[y * width as usize + x] = if is_valid(v) { v } else { fill };
+    }
+
+    Ok(HeightMap {
+        size: [width, height],
+        heights,
+        spacing: cellsize,
+    })
+}
+
+fn crop(map: &HeightMap, min: [u32; 2], max: [u32; 2]) -> io::Result<HeightMap> {
+    if max[0] >= map.size[0] || max[1] >= map.size[1] || min[0] > max[0] || min[1] > max[1] {
+        return Err(invalid_input("region is outside of the map"));
+    }
+    let size = [max[0] - min[0] + 1, max[1] - min[1] + 1];
+    let mut heights = Vec::with_capacity(size[0] as usize * size[1] as usize);
+    for y in min[1]..=max[1] {
+        let row = y as usize * map.size[0] as usize;
+        heights.extend_from_slice(&map.heights[row + min[0] as usize..=row + max[0] as usize]);
+    }
+    Ok(HeightMap {
+        size,
+        heights,
+        spacing: map.spacing,
+    })
+}
+
+/// The largest `--lod`, so that the downsampling factor `2^lod` fits in an `i32`.
+const MAX_LOD: u32 = 30;
+
+/// Returns `None` if the usage was requested instead.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut positional = Vec::new();
+    let mut parsed = Args {
+        input: PathBuf::new(),
+        output: PathBuf::new(),
+        scale: None,
+        vertical_scale: 1.0,
+        lod: 0,
+        lod_policy: DownsamplePolicy::Average,
+        region: None,
+        raw_size: None,
+        raw_range: [0.0, 1.0],
+        raw_big_endian: false,
+        raw_sidecar: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} requires a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--scale" => parsed.scale = Some(parse_value(&value(&arg)?)?),
+            "--vertical-scale" => parsed.vertical_scale = parse_value(&value(&arg)?)?,
+            "--lod" => {
+                parsed.lod = parse_value(&value(&arg)?)?;
+                if parsed.lod > MAX_LOD {
+                    return Err(format!("--lod must be at most {}", MAX_LOD));
+                }
+            }
+            "--lod-policy" => {
+                parsed.lod_policy = match value(&arg)?.as_str() {
+                    "average" => DownsamplePolicy::Average,
+                    "max" => DownsamplePolicy::Max,
+                    "min" => DownsamplePolicy::Min,
+                    other => return Err(format!("unknown --lod-policy {:?}", other)),
+                }
+            }
+            "--region" => {
+                let v = parse_list::<u32>(&value(&arg)?)?;
+                parsed.region = Some(v.try_into().map_err(|_| "--region takes 4 values")?);
+            }
+            "--raw-size" => {
+                let v = parse_list::<u32>(&value(&arg)?)?;
+                parsed.raw_size = Some(v.try_into().map_err(|_| "--raw-size takes 2 values")?);
+            }
+            "--raw-range" => {
+                let v = parse_list::<f32>(&value(&arg)?)?;
+                parsed.raw_range = v.try_into().map_err(|_| "--raw-range takes 2 values")?;
+            }
+            "--raw-big-endian" => parsed.raw_big_endian = true,
+            "--raw-sidecar" => parsed.raw_sidecar = Some(value(&arg)?.into()),
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            _ => positional.push(arg),
+        }
+    }
+
+    let [input, output]: [String; 2] = positional
+        .try_into()
+        .map_err(|_| "expected an input and an output path")?;
+    parsed.input = input.into();
+    parsed.output = output.into();
+    Ok(Some(parsed))
+}
+
+fn parse_value<T: std::str::FromStr>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("invalid value {:?}", s))
+}
+
+fn parse_list<T: std::str::FromStr>(s: &str) -> Result<Vec<T>, String> {
+    s.split(',').map(|v| parse_value(v.trim())).collect()
+}
+
+fn parse<T: std::str::FromStr>(s: &str) -> io::Result<T> {
+    s.parse()
+        .map_err(|_| invalid_input(&format!("invalid value {:?}", s)))
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
@@ -0,0 +1,108 @@
+use crate::normals;
+use crate::HeightMeshBuffer;
+
+use std::collections::{HashMap, HashSet};
+
+impl HeightMeshBuffer {
+    /// Returns every closed loop of boundary vertices, both around the outer perimeter of the mesh and around any holes.
+    ///
+    /// A boundary edge is one that belongs to exactly one triangle. Each loop is a sequence of vertex indices that follows the
+    /// direction of its edges in the triangles that own them, so the perimeter and the holes wind in opposite directions when
+    /// viewed from above. The first vertex is not repeated at the end.
+    ///
+    /// This only depends on `indices`, so it also works on meshes that have been edited or appended together.
+    pub fn boundary_loops(&self) -> Vec<Vec<u32>> {
+        let mut edges = HashSet::new();
+        for tri in self.indices.chunks_exact(3) {
+            edges.insert((tri[0], tri[1]));
+            edges.insert((tri[1], tri[2]));
+            edges.insert((tri[2], tri[0]));
+        }
+
+        // Boundary edges have no twin going the other way. Vertices where two loops touch have multiple outgoing edges.
+        let mut next: HashMap<u32, Vec<u32>> = HashMap::new();
+        for &(a, b) in edges.iter() {
+            if !edges.contains(&(b, a)) {
+                next.entry(a).or_default().push(b);
+            }
+        }
+
+        // Visit start vertices in a deterministic order.
+        let mut starts: Vec<u32> = next.keys().copied().collect();
+        starts.sort_unstable();
+
+        let mut loops = Vec::new();
+        for start in starts {
+            while let Some(first) = next.get_mut(&start).and_then(|n| n.pop()) {
+                let mut boundary_loop = vec![start];
+                let mut v = first;
+                while v != start {
+                    boundary_loop.push(v);
+                    match next.get_mut(&v).and_then(|n| n.pop()) {
+                        Some(n) => v = n,
+                        // Only possible for non-manifold input; return the open chain rather than looping forever.
+                        None => break,
+                    }
+                }
+                loops.push(boundary_loop);
+            }
+        }
+
+        loops
+    }
+
+    /// Averages the normals of the boundary vertices that this mesh shares with an adjacent chunk's mesh, `other`, in place.
+    ///
+    /// Chunks meshed without their neighbors' samples in the padding estimate different normals along their shared border,
+    /// which shows up as a lighting seam. This fixes the seam after the fact. A boundary vertex of `other` that lies exactly
+    /// on a boundary vertex of `self` once translated by `other_offset` is considered shared, as in
+    /// [`HeightMeshBuffer::append`]. Returns the number of shared vertices, or 0 without changing anything if either mesh
+    /// has no normals.
+    pub fn blend_border_normals(
+        &mut self,
+        other: &mut HeightMeshBuffer,
+        other_offset: [f32; 3],
+    ) -> usize {
+        if !self.has_normals() || !other.has_normals() {
+            return 0;
+        }
+        let key = |[x, y, z]: [f32; 3]| [x.to_bits(), y.to_bits(), z.to_bits()];
+
+        let mut border: HashMap<[u32; 3], u32> = HashMap::new();
+        for v in self.boundary_loops().into_iter().flatten() {
+            border.insert(key(self.positions[v as usize]), v);
+        }
+
+        let mut num_shared = 0;
+        for v in other.boundary_loops().into_iter().flatten() {
+            let [x, y, z] = other.positions[v as usize];
+            let p = [
+                x + other_offset[0],
+                y + other_offset[1],
+                z + other_offset[2],
+            ];
+            if let Some(&u) = border.get(&key(p)) {
+                let (Some(a), Some(b)) = (self.normal(u as usize), other.normal(v as usize)) else {
+                    continue;
+                };
+                let average = [
+                    0.5 * (a[0] + b[0]),
+                    0.5 * (a[1] + b[1]),
+                    0.5 * (a[2] + b[2]),
+                ];
+                set_normal(self, u as usize, average);
+                set_normal(other, v as usize, average);
+                num_shared += 1;
+            }
+        }
+        num_shared
+    }
+}
+
+/// Overwrites the normal of vertex `i` in whichever normal channel `buffer` has.
+fn set_normal(buffer: &mut HeightMeshBuffer, i: usize, normal: [f32; 3]) {
+    match buffer.normals.get_mut(i) {
+        Some(n) => *n = normal,
+        None => buffer.packed_normals[i] = normals::pack(normal),
+    }
+}
@@ -0,0 +1,91 @@
+use crate::HeightMapView;
+
+use ndshape::Shape;
+
+/// Parameters for [`traversal_costmap`].
+#[derive(Clone, Copy, Debug)]
+pub struct CostmapConfig {
+    /// The real distance between adjacent samples, in the same units as the heights.
+    pub horizontal_spacing: f32,
+    /// The steepest traversable slope, in radians.
+    pub max_slope: f32,
+    /// The largest traversable height difference within one cell.
+    pub max_step_height: f32,
+    /// The largest traversable roughness (see [`TraversalCell::roughness`]).
+    pub max_roughness: f32,
+}
+
+impl Default for CostmapConfig {
+    fn default() -> Self {
+        Self {
+            horizontal_spacing: 1.0,
+            max_slope: 30f32.to_radians(),
+            max_step_height: 0.25,
+            max_roughness: 0.1,
+        }
+    }
+}
+
+/// The traversability of one cell (quad) of the mesh.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TraversalCell {
+    /// The angle of the cell's best-fit plane from horizontal, in radians.
+    pub slope: f32,
+    /// The difference between the highest and lowest corners of the cell.
+    pub step_height: f32,
+    /// The RMS distance of the corners from the cell's best-fit plane, which is zero for any planar cell.
+    pub roughness: f32,
+    /// The largest of `slope`, `step_height`, and `roughness` as a fraction of its limit in [`CostmapConfig`], so 0 is
+    /// flat and smooth, or infinity when any limit is exceeded.
+    pub cost: f32,
+}
+
+/// Computes a [`TraversalCell`] for every quad that [`height_mesh`](crate::height_mesh) generates for `[min, max]`, for
+/// path planning on the same grid that is rendered.
+///
+/// The costmap is written row-major (X varies fastest) with dimensions `max - min - 2`, where the cell at `[i, j]` is the
+/// quad whose bottom-left corner is sample `min + 1 + [i, j]`.
+pub fn traversal_costmap<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &CostmapConfig,
+    costmap: &mut Vec<TraversalCell>,
+) {
+    assert!((map_shape.linearize(max) as usize) < height_map.len());
+
+    costmap.clear();
+
+    let h = |x: u32, z: u32| height_map.height(map_shape.linearize([x, z]) as usize);
+    let spacing = config.horizontal_spacing;
+
+    for z in min[1] + 1..max[1].saturating_sub(1) {
+        for x in min[0] + 1..max[0].saturating_sub(1) {
+            let [h00, h10, h01, h11] = [h(x, z), h(x + 1, z), h(x, z + 1), h(x + 1, z + 1)];
+
+            // The least squares plane through the four corners has the average gradient of the opposite edges, and every
+            // corner is off the plane by the same amount.
+            let dh_dx = 0.5 * ((h10 - h00) + (h11 - h01)) / spacing;
+            let dh_dz = 0.5 * ((h01 - h00) + (h11 - h10)) / spacing;
+            let slope = dh_dx.hypot(dh_dz).atan();
+            let roughness = 0.25 * (h00 - h10 - h01 + h11).abs();
+
+            let highest = h00.max(h10).max(h01).max(h11);
+            let lowest = h00.min(h10).min(h01).min(h11);
+            let step_height = highest - lowest;
+
+            let cost = (slope / config.max_slope)
+                .max(step_height / config.max_step_height)
+                .max(roughness / config.max_roughness);
+            let cost = if cost > 1.0 { f32::INFINITY } else { cost };
+
+            costmap.push(TraversalCell {
+                slope,
+                step_height,
+                roughness,
+                cost,
+            });
+        }
+    }
+}
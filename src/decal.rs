@@ -0,0 +1,174 @@
+use crate::normals::{add, scale, sub};
+use crate::{HeightMapView, HeightMeshBuffer};
+
+use ndshape::Shape;
+
+/// The footprint of a decal for [`decal_mesh`], in the horizontal `[x, z]` sample coordinates of the mesh.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecalShape {
+    /// A rectangle rotated counterclockwise by `rotation` radians (viewed from above, with +X right and +Z up). U runs along
+    /// the rotated X axis and V along the rotated Z axis.
+    Rectangle {
+        center: [f32; 2],
+        half_extents: [f32; 2],
+        rotation: f32,
+    },
+    /// A circle. The patch covers its bounding square, so the decal texture should be transparent outside of the inscribed
+    /// circle.
+    Circle { center: [f32; 2], radius: f32 },
+}
+
+impl DecalShape {
+    /// The center, half extents, and rotation of the clipping rectangle.
+    fn rectangle(&self) -> ([f32; 2], [f32; 2], f32) {
+        match *self {
+            DecalShape::Rectangle {
+                center,
+                half_extents,
+                rotation,
+            } => (center, half_extents, rotation),
+            DecalShape::Circle { center, radius } => (center, [radius; 2], 0.0),
+        }
+    }
+}
+
+/// A vertex of a polygon being clipped.
+#[derive(Clone, Copy)]
+struct ClipVertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    /// The decal UV, which is also the position in the clipping frame.
+    uv: [f32; 2],
+}
+
+/// Extracts the part of the mesh generated by [`height_mesh`](crate::height_mesh) for `[min, max]` that lies under `shape`,
+/// for decals like roads and blast marks that must hug the terrain exactly.
+///
+/// The mesh triangles are clipped to the decal footprint, so the patch lies exactly on the terrain surface, with the same
+/// interpolated normals. UVs span `[0, 1]` across the footprint. Only positions, normals, UVs, and indices are written to
+/// `output`; the patch has no `stride_to_index` mapping. Use a depth bias when rendering to avoid z-fighting with the
+/// terrain.
+pub fn decal_mesh<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    shape: &DecalShape,
+    output: &mut HeightMeshBuffer,
+) {
+    assert!((map_shape.linearize(max) as usize) < height_map.len());
+
+    output.reset(0);
+
+    let (center, half_extents, rotation) = shape.rectangle();
+    let (sin, cos) = rotation.sin_cos();
+    let to_uv = |[x, _, z]: [f32; 3]| {
+        let [dx, dz] = [x - center[0], z - center[1]];
+        let local = [cos * dx + sin * dz, -sin * dx + cos * dz];
+        [
+            0.5 + 0.5 * local[0] / half_extents[0],
+            0.5 + 0.5 * local[1] / half_extents[1],
+        ]
+    };
+
+    // The axis-aligned bounds of the footprint.
+    let reach = [
+        (cos * half_extents[0]).abs() + (sin * half_extents[1]).abs(),
+        (sin * half_extents[0]).abs() + (cos * half_extents[1]).abs(),
+    ];
+    // Quads have their bottom-left corner in [min + 1, max - 2].
+    let cell_range = |axis: usize| {
+        let lo = (center[axis] - reach[axis])
+            .floor()
+            .max((min[axis] + 1) as f32) as u32;
+        let hi = (center[axis] + reach[axis])
+            .floor()
+            .min(max[axis] as f32 - 2.0);
+        (lo, hi)
+    };
+    let (min_x, max_x) = cell_range(0);
+    let (min_z, max_z) = cell_range(1);
+    if max_x < min_x as f32 || max_z < min_z as f32 {
+        return;
+    }
+    let (max_x, max_z) = (max_x as u32, max_z as u32);
+
+    let vertex = |x: u32, z: u32| {
+        let h = |x: u32, z: u32| height_map.height(map_shape.linearize([x, z]) as usize);
+        let position = [x as f32, h(x, z), z as f32];
+        let normal = [
+            -(h(x + 1, z) - h(x - 1, z)) / 2.0,
+            1.0,
+            -(h(x, z + 1) - h(x, z - 1)) / 2.0,
+        ];
+        ClipVertex {
+            position,
+            normal,
+            uv: to_uv(position),
+        }
+    };
+
+    let mut polygon = Vec::new();
+    let mut scratch = Vec::new();
+    for z in min_z..=max_z {
+        for x in min_x..=max_x {
+            let bl = vertex(x, z);
+            let br = vertex(x + 1, z);
+            let tl = vertex(x, z + 1);
+            let tr = vertex(x + 1, z + 1);
+            for triangle in [[bl, tl, tr], [bl, tr, br]] {
+                polygon.clear();
+                polygon.extend_from_slice(&triangle);
+                for (axis, keep_above) in [(0, true), (0, false), (1, true), (1, false)] {
+                    clip(&polygon, axis, keep_above, &mut scratch);
+                    std::mem::swap(&mut polygon, &mut scratch);
+                }
+                if polygon.len() < 3 {
+                    continue;
+                }
+
+                let first = output.positions.len() as u32;
+                for v in polygon.iter() {
+                    output.positions.push(v.position);
+                    output.normals.push(v.normal);
+                    output.uvs.push(v.uv);
+                }
+                for i in 1..polygon.len() as u32 - 1 {
+                    output
+                        .indices
+                        .extend_from_slice(&[first, first + i, first + i + 1]);
+                }
+            }
+        }
+    }
+}
+
+/// Sutherland-Hodgman clipping of a convex polygon to `uv[axis] >= 0` (or `<= 1` if not `keep_above`).
+fn clip(polygon: &[ClipVertex], axis: usize, keep_above: bool, output: &mut Vec<ClipVertex>) {
+    output.clear();
+    let distance = |v: &ClipVertex| {
+        if keep_above {
+            v.uv[axis]
+        } else {
+            1.0 - v.uv[axis]
+        }
+    };
+    for (i, a) in polygon.iter().enumerate() {
+        let b = &polygon[(i + 1) % polygon.len()];
+        let (da, db) = (distance(a), distance(b));
+        if da >= 0.0 {
+            output.push(*a);
+        }
+        if (da >= 0.0) != (db >= 0.0) {
+            let t = da / (da - db);
+            output.push(ClipVertex {
+                position: add(a.position, scale(sub(b.position, a.position), t)),
+                normal: add(a.normal, scale(sub(b.normal, a.normal), t)),
+                uv: [
+                    a.uv[0] + t * (b.uv[0] - a.uv[0]),
+                    a.uv[1] + t * (b.uv[1] - a.uv[1]),
+                ],
+            });
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::detail::{self, Composited};
+use crate::occluder::coarse_lines;
+use crate::{
+    push_grid_indices, push_normal, snap_to_lattice, vertex_normal, HeightMapView,
+    HeightMeshBuffer, HeightMeshConfig,
+};
+
+use ndshape::Shape;
+
+/// Generates a coarse level of detail of the mesh from [`height_mesh_with_config`](crate::height_mesh_with_config), with a
+/// vertex only every `step` samples of the interior of `[min, max]`.
+///
+/// Unlike meshing a downsampled copy of the map, each vertex keeps the normal of the full-resolution mesh at the same
+/// sample, since it's estimated from the full-resolution gradients. So distant terrain keeps its detailed shading even
+/// though most of its geometry is gone. The last row and column are always included, so the mesh covers the same
+/// footprint as the full-resolution one.
+///
+/// Only the positions, normals, indices, and `stride_to_index` (for the coarse samples) are generated. The height and
+/// normal options of `config` (including [`HeightMeshConfig::packed_normals`]) are honored, as is [`HeightMeshConfig::border_snap`]; the rest are ignored.
+pub fn decimated_height_mesh<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    step: u32,
+    config: &HeightMeshConfig,
+    output: &mut HeightMeshBuffer,
+) {
+    match Composited::new(height_map, map_shape, config) {
+        Some(input) => {
+            let config = detail::remaining_config(config);
+            decimate(&input, map_shape, min, max, step, &config, output)
+        }
+        None => decimate(height_map, map_shape, min, max, step, config, output),
+    }
+}
+
+fn decimate<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    step: u32,
+    config: &HeightMeshConfig,
+    output: &mut HeightMeshBuffer,
+) {
+    assert!(step > 0);
+    assert!((map_shape.linearize(min) as usize) < height_map.len());
+    assert!((map_shape.linearize(max) as usize) < height_map.len());
+
+    output.reset(height_map.len());
+
+    let imin = [min[0] + 1, min[1] + 1];
+    let imax = [max[0] - 1, max[1] - 1];
+    let xs = coarse_lines(imin[0], imax[0], step);
+    let zs = coarse_lines(imin[1], imax[1], step);
+    for &z in zs.iter() {
+        let spacing = match &config.geodetic {
+            Some(geodetic) => geodetic.meters_per_sample(z).map(|d| d as f32),
+            None => [1.0; 2],
+        };
+        for &x in xs.iter() {
+            let stride = map_shape.linearize([x, z]);
+            let y = config.vertical_scale * height_map.height(stride as usize);
+
+            output.stride_to_index[stride as usize] = output.positions.len() as u32;
+            let [px, pz] = match &config.geodetic {
+                Some(geodetic) => geodetic.position([x, z]).map(|p| p as f32),
+                None => [x as f32, z as f32],
+            };
+            let mut position = [px, y, pz];
+            if let Some(lattice) = config.border_snap {
+                if x == imin[0] || x == imax[0] || z == imin[1] || z == imax[1] {
+                    position = snap_to_lattice(position, lattice);
+                }
+            }
+            output.positions.push(position);
+
+            let normal =
+                vertex_normal(height_map, map_shape, [imin, imax], config, [x, z], spacing);
+            push_normal(output, config, normal);
+        }
+    }
+
+    push_grid_indices(xs.len() as u32, zs.len() as u32, &mut output.indices);
+}
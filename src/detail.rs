@@ -0,0 +1,97 @@
+use crate::{HeightMapView, HeightMeshConfig, HeightRemap};
+
+use ndshape::Shape;
+
+/// A small, high-frequency height map tiled over the primary map and added to it during meshing. See
+/// [`HeightMeshConfig::detail`](crate::HeightMeshConfig::detail).
+///
+/// This adds fine surface variation (rocks, ripples, noise) without precomputing a combined map for every chunk.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DetailMap<'a> {
+    /// The row-major detail samples, with X varying fastest.
+    pub heights: &'a [f32],
+    /// The dimensions of `heights`. Both must be nonzero.
+    pub size: [u32; 2],
+    /// Multiplies each detail sample before it is added to the primary height.
+    pub weight: f32,
+    /// Added to the primary map's sample coordinates before wrapping them into the detail map. Set this to the chunk's
+    /// position in the world (in samples), so the tiling lines up across chunks.
+    pub offset: [u32; 2],
+}
+
+impl DetailMap<'_> {
+    /// The weighted detail height at the primary map's sample coordinates `p`.
+    #[inline]
+    pub fn height_at(&self, [x, z]: [u32; 2]) -> f32 {
+        let dx = x.wrapping_add(self.offset[0]) % self.size[0];
+        let dz = z.wrapping_add(self.offset[1]) % self.size[1];
+        self.weight * self.heights[(dz * self.size[0] + dx) as usize]
+    }
+}
+
+/// The height map as the mesher sees it after the on-the-fly adjustments in [`HeightMeshConfig`]: first the detail map is
+/// added, then the heights are remapped.
+pub(crate) struct Composited<'a, 'c, M: ?Sized, S> {
+    view: &'a M,
+    shape: &'a S,
+    detail: Option<DetailMap<'c>>,
+    remap: Option<HeightRemap>,
+}
+
+impl<'a, 'c, M: HeightMapView + ?Sized, S: Shape<u32, 2>> Composited<'a, 'c, M, S> {
+    /// Returns `None` if `config` doesn't adjust the heights at all, so the map can be used directly.
+    pub(crate) fn new(view: &'a M, shape: &'a S, config: &HeightMeshConfig<'c>) -> Option<Self> {
+        if let Some(detail) = &config.detail {
+            assert!(
+                detail.size[0] > 0 && detail.size[1] > 0,
+                "detail map must not be empty"
+            );
+            assert_eq!(
+                detail.heights.len(),
+                detail.size[0] as usize * detail.size[1] as usize
+            );
+        }
+        (config.detail.is_some() || config.height_remap.is_some()).then_some(Self {
+            view,
+            shape,
+            detail: config.detail,
+            remap: config.height_remap,
+        })
+    }
+
+    #[inline]
+    fn adjust(&self, i: usize, h: f32) -> f32 {
+        let mut h = h;
+        if let Some(detail) = &self.detail {
+            h += detail.height_at(self.shape.delinearize(i as u32));
+        }
+        if let Some(remap) = &self.remap {
+            h = remap.apply(h);
+        }
+        h
+    }
+}
+
+impl<M: HeightMapView + ?Sized, S: Shape<u32, 2>> HeightMapView for Composited<'_, '_, M, S> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.view.len()
+    }
+    #[inline]
+    fn height(&self, i: usize) -> f32 {
+        self.adjust(i, self.view.height(i))
+    }
+    #[inline]
+    unsafe fn height_unchecked(&self, i: usize) -> f32 {
+        self.adjust(i, self.view.height_unchecked(i))
+    }
+}
+
+/// `config` without the adjustments that are already applied by [`Composited`].
+pub(crate) fn remaining_config<'c>(config: &HeightMeshConfig<'c>) -> HeightMeshConfig<'c> {
+    HeightMeshConfig {
+        detail: None,
+        height_remap: None,
+        ..config.clone()
+    }
+}
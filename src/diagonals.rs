@@ -0,0 +1,47 @@
+/// Which diagonal splits each quad into two triangles, for
+/// [`HeightMeshConfig::diagonals`](crate::HeightMeshConfig::diagonals).
+///
+/// Every pattern is a pure function of the quad's position in the height map, so remeshing the same region (or a chunk
+/// that overlaps it) always produces the same topology, which baked lightmaps and cached physics rely on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DiagonalPattern {
+    /// Every quad is split from its bottom-left to its top-right corner.
+    #[default]
+    Uniform,
+    /// The diagonal alternates in a checkerboard, which avoids the directional bias of a uniform split.
+    Alternating,
+    /// The diagonal of each quad is chosen pseudo-randomly from the seed and the quad's position.
+    Seeded(u64),
+}
+
+impl DiagonalPattern {
+    /// Returns true if the quad whose bottom-left corner is sample `[x, z]` is split from its top-left to its bottom-right
+    /// corner instead.
+    #[inline]
+    pub fn is_flipped(&self, [x, z]: [u32; 2]) -> bool {
+        match *self {
+            DiagonalPattern::Uniform => false,
+            DiagonalPattern::Alternating => (x + z) % 2 == 1,
+            DiagonalPattern::Seeded(seed) => {
+                splitmix64(seed ^ ((x as u64) << 32 | z as u64)) & 1 == 1
+            }
+        }
+    }
+}
+
+/// The two triangles of the quad with the given corners, with the same winding as [`height_mesh`](crate::height_mesh).
+#[inline]
+pub(crate) fn quad_indices<T: Copy>([bl, br, tl, tr]: [T; 4], flipped: bool) -> [T; 6] {
+    if flipped {
+        [bl, tl, br, tl, tr, br]
+    } else {
+        [bl, tl, tr, bl, tr, br]
+    }
+}
+
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
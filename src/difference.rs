@@ -0,0 +1,84 @@
+use crate::{height_mesh, HeightMapView, HeightMeshBuffer};
+
+use ndshape::Shape;
+
+/// Which heights are used for the vertex positions of a [`height_difference_mesh`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DifferenceSurface {
+    /// Positions follow the `after` height map, so the difference is only shown by color.
+    #[default]
+    After,
+    /// Positions are displaced by `after - before`, so unchanged areas are flat at zero height.
+    Difference,
+}
+
+/// Parameters for [`height_difference_mesh`].
+#[derive(Clone, Copy, Debug)]
+pub struct HeightDifferenceConfig {
+    /// Which heights to use for vertex positions.
+    pub surface: DifferenceSurface,
+    /// The magnitude of difference that maps to a fully saturated color. Larger differences are clamped.
+    pub color_range: f32,
+}
+
+impl Default for HeightDifferenceConfig {
+    fn default() -> Self {
+        Self {
+            surface: DifferenceSurface::After,
+            color_range: 1.0,
+        }
+    }
+}
+
+/// Generates a mesh like [`height_mesh`] that visualizes the change from `before` to `after`, which must have the same shape.
+///
+/// Each vertex is colored with a diverging ramp written to [`HeightMeshBuffer::colors`]: blue where height was removed,
+/// white where it is unchanged, and red where height was added. This is intended for debugging erosion, reviewing terrain
+/// edits, and survey change detection.
+pub fn height_difference_mesh<B, A, S>(
+    before: &B,
+    after: &A,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &HeightDifferenceConfig,
+    output: &mut HeightMeshBuffer,
+) where
+    B: HeightMapView + ?Sized,
+    A: HeightMapView + ?Sized,
+    S: Shape<u32, 2>,
+{
+    assert_eq!(before.len(), after.len());
+
+    match config.surface {
+        DifferenceSurface::After => height_mesh(after, map_shape, min, max, output),
+        DifferenceSurface::Difference => {
+            let difference: Vec<f32> = (0..after.len())
+                .map(|i| after.height(i) - before.height(i))
+                .collect();
+            height_mesh(&difference, map_shape, min, max, output);
+        }
+    }
+
+    let [minx, miny] = min;
+    let [maxx, maxy] = max;
+    for z in miny + 1..maxy {
+        for x in minx + 1..maxx {
+            let stride = map_shape.linearize([x, z]) as usize;
+            let t = ((after.height(stride) - before.height(stride)) / config.color_range)
+                .clamp(-1.0, 1.0);
+            output.colors.push(diverging_color(t));
+        }
+    }
+}
+
+/// Maps `t` in `[-1, 1]` to blue, white, and red.
+fn diverging_color(t: f32) -> [f32; 4] {
+    if t < 0.0 {
+        let s = 1.0 + t;
+        [s, s, 1.0, 1.0]
+    } else {
+        let s = 1.0 - t;
+        [1.0, s, s, 1.0]
+    }
+}
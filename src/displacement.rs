@@ -0,0 +1,174 @@
+use crate::{height_mesh, HeightMapView, HeightMeshBuffer};
+
+use ndshape::Shape;
+
+/// A tightly-packed, single-channel height texture for vertex displacement in a shader.
+#[derive(Clone, Debug, Default)]
+pub struct HeightTexture {
+    /// The dimensions of the texture in texels.
+    pub size: [u32; 2],
+    /// Row-major heights (X varies fastest), suitable for an `R32F` texture.
+    pub texels: Vec<f32>,
+}
+
+impl HeightTexture {
+    /// Converts the texels to IEEE half-precision bit patterns for an `R16F` texture, rounding to nearest even.
+    pub fn to_f16_bits(&self) -> Vec<u16> {
+        self.texels.iter().map(|&h| f32_to_f16_bits(h)).collect()
+    }
+}
+
+/// Generates a flat (`y = 0`) grid mesh with the same topology as [`height_mesh`], plus a [`HeightTexture`] holding every
+/// sample in `[min, max]`, for renderers that displace vertices in the shader.
+///
+/// The texture includes the boundary samples, so a shader can reproduce the central-difference normals exactly. The UVs
+/// written to [`HeightMeshBuffer::uvs`] address the center of each vertex's texel, so point or bilinear sampling both return
+/// the exact height. All normals point straight up.
+pub fn displacement_grid<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    output: &mut HeightMeshBuffer,
+    texture: &mut HeightTexture,
+) {
+    height_mesh(height_map, map_shape, min, max, output);
+
+    let [minx, miny] = min;
+    let [maxx, maxy] = max;
+    let size = [maxx - minx + 1, maxy - miny + 1];
+
+    texture.size = size;
+    texture.texels.clear();
+    for y in miny..=maxy {
+        for x in minx..=maxx {
+            texture
+                .texels
+                .push(height_map.height(map_shape.linearize([x, y]) as usize));
+        }
+    }
+
+    for (p, n) in output.positions.iter_mut().zip(output.normals.iter_mut()) {
+        let [x, _, z] = *p;
+        output.uvs.push([
+            (x - minx as f32 + 0.5) / size[0] as f32,
+            (z - miny as f32 + 0.5) / size[1] as f32,
+        ]);
+        *p = [x, 0.0, z];
+        *n = [0.0, 1.0, 0.0];
+    }
+}
+
+fn f32_to_f16_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // Infinity or NaN (keeping NaN quiet).
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+
+    let half_exponent = exponent - 127 + 15;
+    if half_exponent >= 0x1f {
+        // Overflow to infinity.
+        return sign | 0x7c00;
+    }
+
+    if half_exponent <= 0 {
+        if half_exponent < -10 {
+            // Underflow to zero.
+            return sign;
+        }
+        // Subnormal: shift the mantissa (with its implicit bit) into place.
+        let full_mantissa = mantissa | 0x80_0000;
+        let shift = (14 - half_exponent) as u32;
+        let mut half = full_mantissa >> shift;
+        let remainder = full_mantissa & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        if remainder > halfway || (remainder == halfway && half & 1 == 1) {
+            half += 1;
+        }
+        return sign | half as u16;
+    }
+
+    let mut half = ((half_exponent as u32) << 10) | (mantissa >> 13);
+    let remainder = mantissa & 0x1fff;
+    // A carry out of the mantissa correctly bumps the exponent, possibly to infinity.
+    if remainder > 0x1000 || (remainder == 0x1000 && half & 1 == 1) {
+        half += 1;
+    }
+    sign | half as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f16_normal_values() {
+        assert_eq!(f32_to_f16_bits(0.0), 0x0000);
+        assert_eq!(f32_to_f16_bits(1.0), 0x3c00);
+        assert_eq!(f32_to_f16_bits(-2.0), 0xc000);
+        assert_eq!(f32_to_f16_bits(0.5), 0x3800);
+        assert_eq!(f32_to_f16_bits(65504.0), 0x7bff);
+        // The smallest normal half.
+        assert_eq!(f32_to_f16_bits(2f32.powi(-14)), 0x0400);
+    }
+
+    #[test]
+    fn f16_negative_zero() {
+        assert_eq!(f32_to_f16_bits(-0.0), 0x8000);
+        // Negative values too small for a half keep their sign.
+        assert_eq!(f32_to_f16_bits(-1e-10), 0x8000);
+    }
+
+    #[test]
+    fn f16_subnormals() {
+        assert_eq!(f32_to_f16_bits(2f32.powi(-15)), 0x0200);
+        assert_eq!(f32_to_f16_bits(2f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_f16_bits(-2f32.powi(-24)), 0x8001);
+        assert_eq!(f32_to_f16_bits(3.0 * 2f32.powi(-24)), 0x0003);
+        // Halfway between 0 and the smallest subnormal rounds to even (zero), and anything less underflows.
+        assert_eq!(f32_to_f16_bits(2f32.powi(-25)), 0x0000);
+        assert_eq!(f32_to_f16_bits(2f32.powi(-26)), 0x0000);
+        // Halfway between 1 and 2 times the smallest subnormal rounds to even (2).
+        assert_eq!(f32_to_f16_bits(3.0 * 2f32.powi(-25)), 0x0002);
+        // Rounding up the largest subnormal carries into the smallest normal.
+        assert_eq!(f32_to_f16_bits(2f32.powi(-14) - 2f32.powi(-25)), 0x0400);
+    }
+
+    #[test]
+    fn f16_rounding_ties() {
+        // The halves in [1, 2) are 2^-10 apart.
+        let ulp = 2f32.powi(-10);
+        assert_eq!(f32_to_f16_bits(1.0 + 0.5 * ulp), 0x3c00);
+        assert_eq!(f32_to_f16_bits(1.0 + 1.5 * ulp), 0x3c02);
+        assert_eq!(f32_to_f16_bits(1.0 + 0.5 * ulp + 2f32.powi(-20)), 0x3c01);
+        assert_eq!(f32_to_f16_bits(1.0 + 0.5 * ulp - 2f32.powi(-20)), 0x3c00);
+        // A carry out of the mantissa bumps the exponent.
+        assert_eq!(f32_to_f16_bits(2.0 - 0.5 * ulp), 0x4000);
+    }
+
+    #[test]
+    fn f16_overflow_to_infinity() {
+        // Halfway between the largest half and 2^16 rounds to even, which is infinity.
+        assert_eq!(f32_to_f16_bits(65519.0), 0x7bff);
+        assert_eq!(f32_to_f16_bits(65520.0), 0x7c00);
+        assert_eq!(f32_to_f16_bits(1e10), 0x7c00);
+        assert_eq!(f32_to_f16_bits(-1e10), 0xfc00);
+        assert_eq!(f32_to_f16_bits(f32::INFINITY), 0x7c00);
+        assert_eq!(f32_to_f16_bits(f32::NEG_INFINITY), 0xfc00);
+    }
+
+    #[test]
+    fn f16_nan() {
+        let is_nan = |h: u16| h & 0x7c00 == 0x7c00 && h & 0x03ff != 0;
+        assert!(is_nan(f32_to_f16_bits(f32::NAN)));
+        assert!(is_nan(f32_to_f16_bits(-f32::NAN)));
+        // A NaN whose payload is only in the low bits, which a plain shift would turn into infinity.
+        assert!(is_nan(f32_to_f16_bits(f32::from_bits(0x7f80_0001))));
+        assert_eq!(f32_to_f16_bits(-f32::NAN) & 0x8000, 0x8000);
+    }
+}
@@ -0,0 +1,43 @@
+use crate::HeightMapView;
+
+/// How [`downsample`] combines each 2x2 block of samples into one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DownsamplePolicy {
+    /// The mean of the block. Smooth, but peaks sink and valleys fill in as the resolution drops.
+    #[default]
+    Average,
+    /// The highest sample of the block, so the result is never below the original surface. Good for conservative LODs and
+    /// occlusion proxies.
+    Max,
+    /// The lowest sample of the block, so the result is never above the original surface.
+    Min,
+}
+
+/// Halves the resolution of a row-major map of `size` samples by combining each 2x2 block with `policy`.
+///
+/// An odd trailing row or column is combined with itself. Returns the downsampled heights and their size, which is `size`
+/// divided by 2 and rounded up. Apply it repeatedly for coarser LODs.
+pub fn downsample<M: HeightMapView + ?Sized>(
+    height_map: &M,
+    size: [u32; 2],
+    policy: DownsamplePolicy,
+) -> (Vec<f32>, [u32; 2]) {
+    let [w, h] = size;
+    assert_eq!((w * h) as usize, height_map.len());
+
+    let out_size = [w.div_ceil(2), h.div_ceil(2)];
+    let get = |x: u32, y: u32| height_map.height((y.min(h - 1) * w + x.min(w - 1)) as usize);
+    let mut heights = Vec::with_capacity(out_size[0] as usize * out_size[1] as usize);
+    for y in 0..out_size[1] {
+        for x in 0..out_size[0] {
+            let (x, y) = (2 * x, 2 * y);
+            let block = [get(x, y), get(x + 1, y), get(x, y + 1), get(x + 1, y + 1)];
+            heights.push(match policy {
+                DownsamplePolicy::Average => block.iter().sum::<f32>() / 4.0,
+                DownsamplePolicy::Max => block.into_iter().fold(f32::NEG_INFINITY, f32::max),
+                DownsamplePolicy::Min => block.into_iter().fold(f32::INFINITY, f32::min),
+            });
+        }
+    }
+    (heights, out_size)
+}
@@ -0,0 +1,438 @@
+//! A compact binary encoding of [`HeightMeshBuffer`] geometry, for streaming server-meshed terrain to clients.
+//!
+//! Positions are quantized to a fixed precision and delta-encoded, normals are octahedron-encoded, and indices are
+//! delta-encoded, all as zigzag varints. Because the mesher emits vertices and triangles in row-major order, consecutive
+//! values are close together and most of them fit in a single byte.
+//!
+//! ```
+//! use height_mesh::encoding::{decode, encode, EncodeOptions};
+//! use height_mesh::ndshape::{ConstShape, ConstShape2u32};
+//! use height_mesh::{height_mesh, HeightMeshBuffer};
+//!
+//! type ChunkShape = ConstShape2u32<18, 18>;
+//! let height_map = [3.0; ChunkShape::SIZE as usize];
+//! let mut buffer = HeightMeshBuffer::default();
+//! height_mesh(&height_map, &ChunkShape {}, [0; 2], [17; 2], &mut buffer);
+//!
+//! let bytes = encode(&buffer, &EncodeOptions::default());
+//! let decoded = decode(&bytes).unwrap();
+//! assert_eq!(decoded.indices, buffer.indices);
+//! assert_eq!(decoded.positions, buffer.positions);
+//! ```
+
+use crate::{normals, HeightMeshBuffer};
+
+use std::fmt;
+
+const MAGIC: &[u8; 4] = b"HMSH";
+const VERSION: u8 = 2;
+
+/// Set in the flags byte if the encoded mesh has normals.
+const HAS_NORMALS: u8 = 1 << 0;
+/// Set in the flags byte if the normals should be decoded into [`HeightMeshBuffer::packed_normals`].
+const PACKED_NORMALS: u8 = 1 << 1;
+
+/// Precision settings for [`encode`].
+#[derive(Clone, Copy, Debug)]
+pub struct EncodeOptions {
+    /// Positions are rounded to multiples of this value.
+    pub position_precision: f32,
+    /// The number of bits for each of the two octahedral normal components, from 2 to 16. 10 bits is visually lossless for
+    /// most lighting.
+    pub normal_bits: u8,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            position_precision: 1.0 / 1024.0,
+            normal_bits: 10,
+        }
+    }
+}
+
+/// Why [`decode`] rejected its input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input does not start with the expected magic bytes.
+    BadMagic,
+    /// The input was written by an incompatible version of the encoding.
+    UnsupportedVersion(u8),
+    /// The input ended in the middle of a value.
+    UnexpectedEnd,
+    /// A value is out of range, e.g. an index that refers to a vertex that doesn't exist.
+    InvalidData,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BadMagic => write!(f, "not an encoded height mesh"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported encoding version {}", v),
+            Self::UnexpectedEnd => write!(f, "unexpected end of input"),
+            Self::InvalidData => write!(f, "invalid data"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Encodes the positions, normals, and indices of `buffer`. Other channels are not included.
+///
+/// Normals are read from either [`HeightMeshBuffer::normals`] or [`HeightMeshBuffer::packed_normals`], and [`decode`]
+/// restores them into the same channel. A mesh without normals is encoded without them.
+pub fn encode(buffer: &HeightMeshBuffer, options: &EncodeOptions) -> Vec<u8> {
+    assert!(options.position_precision > 0.0);
+    assert!((2..=16).contains(&options.normal_bits));
+
+    let mut flags = 0;
+    if buffer.has_normals() {
+        flags |= HAS_NORMALS;
+        if buffer.normals.len() != buffer.positions.len() {
+            flags |= PACKED_NORMALS;
+        }
+    }
+
+    let mut out = Vec::with_capacity(16 + 4 * buffer.positions.len() + buffer.indices.len());
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    out.extend_from_slice(&options.position_precision.to_le_bytes());
+    out.push(options.normal_bits);
+    out.push(flags);
+    write_varint(&mut out, buffer.positions.len() as u64);
+    write_varint(&mut out, buffer.indices.len() as u64);
+
+    let inv_precision = 1.0 / options.position_precision;
+    let mut prev = [0i64; 3];
+    for p in buffer.positions.iter() {
+        for (c, prev_c) in p.iter().zip(prev.iter_mut()) {
+            let q = (c * inv_precision).round() as i64;
+            write_varint(&mut out, zigzag(q - *prev_c));
+            *prev_c = q;
+        }
+    }
+
+    let max = ((1i32 << (options.normal_bits - 1)) - 1) as f32;
+    let mut prev = [0i64; 2];
+    let num_normals = if flags & HAS_NORMALS != 0 {
+        buffer.positions.len()
+    } else {
+        0
+    };
+    for i in 0..num_normals {
+        let [u, v] = oct_encode(buffer.normal(i).unwrap());
+        for (c, prev_c) in [u, v].iter().zip(prev.iter_mut()) {
+            let q = (c * max).round() as i64;
+            write_varint(&mut out, zigzag(q - *prev_c));
+            *prev_c = q;
+        }
+    }
+
+    let mut prev = 0i64;
+    for &i in buffer.indices.iter() {
+        write_varint(&mut out, zigzag(i as i64 - prev));
+        prev = i as i64;
+    }
+
+    out
+}
+
+/// Decodes a buffer written by [`encode`].
+///
+/// The decoded normals are unit length, unlike the normals produced by the mesher. All other channels are empty.
+pub fn decode(bytes: &[u8]) -> Result<HeightMeshBuffer, DecodeError> {
+    let mut reader = Reader { bytes, pos: 0 };
+    if reader.take(4)? != MAGIC {
+        return Err(DecodeError::BadMagic);
+    }
+    let version = reader.take(1)?[0];
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+    let precision = f32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+    let normal_bits = reader.take(1)?[0];
+    let flags = reader.take(1)?[0];
+    if precision.is_nan()
+        || precision <= 0.0
+        || !(2..=16).contains(&normal_bits)
+        || flags & !(HAS_NORMALS | PACKED_NORMALS) != 0
+    {
+        return Err(DecodeError::InvalidData);
+    }
+    let num_vertices = reader.varint()? as usize;
+    let num_indices = reader.varint()? as usize;
+    let num_normals = if flags & HAS_NORMALS != 0 {
+        num_vertices
+    } else {
+        0
+    };
+    // Every value takes at least one byte, so this rejects absurd counts before allocating.
+    let min_len = num_vertices
+        .saturating_mul(3)
+        .saturating_add(num_normals.saturating_mul(2))
+        .saturating_add(num_indices);
+    if min_len > bytes.len() {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+
+    let mut buffer = HeightMeshBuffer::default();
+    buffer.positions.reserve(num_vertices);
+    if flags & PACKED_NORMALS != 0 {
+        buffer.packed_normals.reserve(num_normals);
+    } else {
+        buffer.normals.reserve(num_normals);
+    }
+    buffer.indices.reserve(num_indices);
+
+    let mut prev = [0i64; 3];
+    for _ in 0..num_vertices {
+        let mut p = [0.0; 3];
+        for (c, prev_c) in p.iter_mut().zip(prev.iter_mut()) {
+            reader.delta(prev_c)?;
+            *c = *prev_c as f32 * precision;
+        }
+        buffer.positions.push(p);
+    }
+
+    let max = ((1i32 << (normal_bits - 1)) - 1) as f32;
+    let mut prev = [0i64; 2];
+    for _ in 0..num_normals {
+        let mut uv = [0.0; 2];
+        for (c, prev_c) in uv.iter_mut().zip(prev.iter_mut()) {
+            reader.delta(prev_c)?;
+            *c = (*prev_c as f32 / max).clamp(-1.0, 1.0);
+        }
+        let normal = oct_decode(uv);
+        if flags & PACKED_NORMALS != 0 {
+            buffer.packed_normals.push(normals::pack(normal));
+        } else {
+            buffer.normals.push(normal);
+        }
+    }
+
+    let mut prev = 0i64;
+    for _ in 0..num_indices {
+        reader.delta(&mut prev)?;
+        if prev < 0 || prev as usize >= num_vertices {
+            return Err(DecodeError::InvalidData);
+        }
+        buffer.indices.push(prev as u32);
+    }
+
+    Ok(buffer)
+}
+
+/// Maps a direction onto the `[-1, 1]^2` square by projecting it onto an octahedron and unfolding the lower half.
+fn oct_encode([x, y, z]: [f32; 3]) -> [f32; 2] {
+    let l1 = x.abs() + y.abs() + z.abs();
+    if l1 == 0.0 {
+        return [0.0, 0.0];
+    }
+    // Y is up, so the upper hemisphere (where terrain normals live) occupies the center of the square.
+    let (u, v) = (x / l1, z / l1);
+    if y >= 0.0 {
+        [u, v]
+    } else {
+        [(1.0 - v.abs()) * u.signum(), (1.0 - u.abs()) * v.signum()]
+    }
+}
+
+fn oct_decode([u, v]: [f32; 2]) -> [f32; 3] {
+    let y = 1.0 - u.abs() - v.abs();
+    let (x, z) = if y >= 0.0 {
+        (u, v)
+    } else {
+        ((1.0 - v.abs()) * u.signum(), (1.0 - u.abs()) * v.signum())
+    };
+    let length = (x * x + y * y + z * z).sqrt();
+    [x / length, y / length, z / length]
+}
+
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+fn unzigzag(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self.pos.checked_add(n).ok_or(DecodeError::UnexpectedEnd)?;
+        let slice = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::InvalidData)
+    }
+
+    /// Reads a zigzag varint and adds it to `prev`, rejecting sums that overflow.
+    fn delta(&mut self, prev: &mut i64) -> Result<(), DecodeError> {
+        let delta = unzigzag(self.varint()?);
+        *prev = prev.checked_add(delta).ok_or(DecodeError::InvalidData)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{height_mesh_with_config, HeightMeshConfig};
+    use ndshape::{ConstShape, ConstShape2u32};
+
+    /// A single quad whose positions are multiples of the default precision and whose normals are exactly representable.
+    fn quad() -> HeightMeshBuffer {
+        HeightMeshBuffer {
+            positions: vec![
+                [0.0, 1.5, 0.0],
+                [1.0, -0.25, 0.0],
+                [0.0, 2.0, 1.0],
+                [1.0, 1024.0, 1.0],
+            ],
+            indices: vec![0, 2, 3, 0, 3, 1],
+            ..Default::default()
+        }
+    }
+
+    fn round_trip(buffer: &HeightMeshBuffer) -> HeightMeshBuffer {
+        decode(&encode(buffer, &EncodeOptions::default())).unwrap()
+    }
+
+    #[test]
+    fn round_trip_normals() {
+        let mut buffer = quad();
+        buffer.normals = vec![
+            [0.0, 1.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 0.0, -1.0],
+            [0.0, -1.0, 0.0],
+        ];
+        let decoded = round_trip(&buffer);
+        assert_eq!(decoded.positions, buffer.positions);
+        assert_eq!(decoded.normals, buffer.normals);
+        assert!(decoded.packed_normals.is_empty());
+        assert_eq!(decoded.indices, buffer.indices);
+    }
+
+    #[test]
+    fn round_trip_packed_normals() {
+        let mut buffer = quad();
+        buffer.packed_normals = vec![
+            [0, 127, 0, 0],
+            [127, 0, 0, 0],
+            [0, 0, -127, 0],
+            [-127, 0, 0, 0],
+        ];
+        let decoded = round_trip(&buffer);
+        assert_eq!(decoded.positions, buffer.positions);
+        assert!(decoded.normals.is_empty());
+        assert_eq!(decoded.packed_normals, buffer.packed_normals);
+        assert_eq!(decoded.indices, buffer.indices);
+    }
+
+    #[test]
+    fn round_trip_without_normals() {
+        let buffer = quad();
+        let decoded = round_trip(&buffer);
+        assert_eq!(decoded.positions, buffer.positions);
+        assert!(decoded.normals.is_empty() && decoded.packed_normals.is_empty());
+        assert_eq!(decoded.indices, buffer.indices);
+    }
+
+    #[test]
+    fn round_trip_packed_mesh() {
+        type ChunkShape = ConstShape2u32<10, 10>;
+        let heights: Vec<f32> = (0..ChunkShape::SIZE)
+            .map(|i| (i % 7) as f32 * 0.25)
+            .collect();
+        let config = HeightMeshConfig {
+            packed_normals: true,
+            ..Default::default()
+        };
+        let mut buffer = HeightMeshBuffer::default();
+        height_mesh_with_config(
+            &heights,
+            &ChunkShape {},
+            [0; 2],
+            [9; 2],
+            &config,
+            &mut buffer,
+        );
+
+        let decoded = round_trip(&buffer);
+        assert_eq!(decoded.positions, buffer.positions);
+        assert_eq!(decoded.indices, buffer.indices);
+        // The octahedral encoding is lossy, but well within the precision of the packed bytes.
+        assert_eq!(decoded.packed_normals.len(), buffer.packed_normals.len());
+        for (a, b) in decoded
+            .packed_normals
+            .iter()
+            .zip(buffer.packed_normals.iter())
+        {
+            assert!(
+                a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() <= 1),
+                "{:?} != {:?}",
+                a,
+                b
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_overflowing_deltas() {
+        // Two vertices whose coordinates are each pushed past the i64 range by the largest possible deltas.
+        for delta in [u64::MAX, u64::MAX - 1] {
+            let mut bytes = MAGIC.to_vec();
+            bytes.push(VERSION);
+            bytes.extend_from_slice(&1.0f32.to_le_bytes());
+            bytes.extend_from_slice(&[10, 0]);
+            write_varint(&mut bytes, 2);
+            write_varint(&mut bytes, 0);
+            for _ in 0..6 {
+                write_varint(&mut bytes, delta);
+            }
+            assert!(matches!(decode(&bytes), Err(DecodeError::InvalidData)));
+        }
+    }
+
+    #[test]
+    fn rejects_one_normal_bit() {
+        let mut bytes = encode(&quad(), &EncodeOptions::default());
+        bytes[9] = 1;
+        assert!(matches!(decode(&bytes), Err(DecodeError::InvalidData)));
+    }
+
+    #[test]
+    fn rejects_unknown_flags() {
+        let mut bytes = encode(&quad(), &EncodeOptions::default());
+        bytes[10] = 0x80;
+        assert!(matches!(decode(&bytes), Err(DecodeError::InvalidData)));
+    }
+}
@@ -0,0 +1,680 @@
+//! Writers for common mesh file formats.
+
+use crate::diagonals;
+use crate::normals::{cross, normalize, sub};
+use crate::{
+    height_mesh_window, snap_to_lattice, HeightMapView, HeightMeshBuffer, HeightMeshConfig,
+};
+
+use ndshape::{Shape, Shape2u32};
+use std::io::{self, Write};
+use std::ops::Range;
+
+/// The number of rows of vertices meshed at a time by the streaming writers.
+const STRIP_ROWS: u32 = 64;
+
+/// Writes the mesh as a Wavefront OBJ file.
+///
+/// Positions, normals (if present, packed or not), and [`HeightMeshBuffer::uvs`] (if present) are written. If the buffer
+/// has [`HeightMeshBuffer::metadata`], it is recorded as comments at the top of the file.
+///
+/// `writer` should be buffered, since this performs many small writes.
+pub fn write_obj<W: Write>(buffer: &HeightMeshBuffer, mut writer: W) -> io::Result<()> {
+    if let Some(metadata) = &buffer.metadata {
+        for line in metadata.comment_lines() {
+            writeln!(writer, "# {}", line)?;
+        }
+    }
+
+    for [x, y, z] in buffer.positions.iter() {
+        writeln!(writer, "v {} {} {}", x, y, z)?;
+    }
+    let has_normals = buffer.has_normals() && !buffer.positions.is_empty();
+    if has_normals {
+        for i in 0..buffer.positions.len() {
+            let [x, y, z] = buffer.normal(i).unwrap();
+            writeln!(writer, "vn {} {} {}", x, y, z)?;
+        }
+    }
+    let has_uvs = !buffer.uvs.is_empty();
+    for [u, v] in buffer.uvs.iter() {
+        writeln!(writer, "vt {} {}", u, v)?;
+    }
+
+    // OBJ indices are 1-based.
+    for tri in buffer.indices.chunks_exact(3) {
+        let [a, b, c] = [tri[0] + 1, tri[1] + 1, tri[2] + 1];
+        match (has_uvs, has_normals) {
+            (true, true) => writeln!(writer, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?,
+            (false, true) => writeln!(writer, "f {a}//{a} {b}//{b} {c}//{c}")?,
+            (true, false) => writeln!(writer, "f {a}/{a} {b}/{b} {c}/{c}")?,
+            (false, false) => writeln!(writer, "f {a} {b} {c}")?,
+        }
+    }
+
+    writer.flush()
+}
+
+/// Writes the mesh as a binary STL file, with one facet normal per triangle computed from its vertices.
+///
+/// STL has no notion of shared vertices or units, so this is mostly useful for 3D printing and CAD tools.
+pub fn write_stl<W: Write>(buffer: &HeightMeshBuffer, mut writer: W) -> io::Result<()> {
+    // The 80-byte header must not start with "solid", or some readers assume the ASCII format.
+    let mut header = [b' '; 80];
+    let title = b"height-mesh binary STL";
+    header[..title.len()].copy_from_slice(title);
+    writer.write_all(&header)?;
+
+    let num_triangles = buffer.indices.len() / 3;
+    writer.write_all(&(num_triangles as u32).to_le_bytes())?;
+    for tri in buffer.indices.chunks_exact(3) {
+        let vertices = [
+            buffer.positions[tri[0] as usize],
+            buffer.positions[tri[1] as usize],
+            buffer.positions[tri[2] as usize],
+        ];
+        write_stl_facet(&mut writer, vertices)?;
+    }
+
+    writer.flush()
+}
+
+/// Meshes `[min, max]` like [`height_mesh_with_config`] and writes it as a Wavefront OBJ file, a strip of rows at a time,
+/// so the whole mesh is never held in memory.
+///
+/// The file has the same positions, normals, and faces as [`write_obj`] would write for the full mesh. Only the positions
+/// and normals are written, so the per-vertex channels and the options that need the whole mesh at once (LODs, shadow
+/// casters, ambient occlusion, tessellation, and slope partitioning) are ignored.
+///
+/// `writer` should be buffered, since this performs many small writes.
+pub fn write_obj_streaming<M, S, W>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &HeightMeshConfig,
+    mut writer: W,
+) -> io::Result<()>
+where
+    M: HeightMapView + ?Sized,
+    S: Shape<u32, 2>,
+    W: Write,
+{
+    let first_row = min[1] + 1;
+    for_each_strip(height_map, map_shape, min, max, config, |strip| {
+        let vertices = strip.vertices(strip.new_rows(first_row));
+        for [x, y, z] in strip.buffer.positions[vertices.clone()].iter() {
+            writeln!(writer, "v {} {} {}", x, y, z)?;
+        }
+        for [x, y, z] in strip.buffer.normals[vertices].iter() {
+            writeln!(writer, "vn {} {} {}", x, y, z)?;
+        }
+
+        // OBJ indices are 1-based, and count every vertex written so far.
+        let offset = (strip.mesh_rows.start - first_row) * strip.width + 1;
+        for tri in strip.triangles().chunks_exact(3) {
+            let [a, b, c] = [tri[0] + offset, tri[1] + offset, tri[2] + offset];
+            writeln!(writer, "f {a}//{a} {b}//{b} {c}//{c}")?;
+        }
+        Ok(())
+    })?;
+
+    writer.flush()
+}
+
+/// Meshes `[min, max]` like [`height_mesh_with_config`] and writes it as a binary STL file, a strip of rows at a time, so
+/// the whole mesh is never held in memory.
+///
+/// The facets are the same as [`write_stl`] would write for the full mesh, and the same options are ignored as for
+/// [`write_obj_streaming`].
+pub fn write_stl_streaming<M, S, W>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &HeightMeshConfig,
+    mut writer: W,
+) -> io::Result<()>
+where
+    M: HeightMapView + ?Sized,
+    S: Shape<u32, 2>,
+    W: Write,
+{
+    let mut header = [b' '; 80];
+    let title = b"height-mesh binary STL";
+    header[..title.len()].copy_from_slice(title);
+    writer.write_all(&header)?;
+
+    let cells = [max[0] - min[0], max[1] - min[1]].map(|s| s.saturating_sub(2));
+    let num_triangles = 2 * cells[0] * cells[1];
+    writer.write_all(&num_triangles.to_le_bytes())?;
+    for_each_strip(height_map, map_shape, min, max, config, |strip| {
+        for tri in strip.triangles().chunks_exact(3) {
+            let vertices = [
+                strip.buffer.positions[tri[0] as usize],
+                strip.buffer.positions[tri[1] as usize],
+                strip.buffer.positions[tri[2] as usize],
+            ];
+            write_stl_facet(&mut writer, vertices)?;
+        }
+        Ok(())
+    })?;
+
+    writer.flush()
+}
+
+/// Meshes `[min, max]` like [`height_mesh_with_config`] and writes it as a binary glTF 2.0 (`.glb`) file, a strip of rows
+/// at a time, so the whole mesh is never held in memory.
+///
+/// The file has the same positions, unit normals, and `u32` indices as [`write_gltf`] would write for the full mesh, with
+/// the positions and normals interleaved in one buffer view. The same options are ignored as for [`write_obj_streaming`].
+/// glTF needs the bounds of the positions before any of the data, so each strip is meshed twice: once to find the bounds
+/// and once to write its vertices.
+///
+/// `writer` should be buffered, since this performs many small writes.
+pub fn write_glb_streaming<M, S, W>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &HeightMeshConfig,
+    mut writer: W,
+) -> io::Result<()>
+where
+    M: HeightMapView + ?Sized,
+    S: Shape<u32, 2>,
+    W: Write,
+{
+    assert!(max[0] >= min[0] + 2 && max[1] >= min[1] + 2);
+
+    let imin = [min[0] + 1, min[1] + 1];
+    let size = [max[0] - min[0] - 1, max[1] - min[1] - 1];
+    let num_vertices = size[0] as u64 * size[1] as u64;
+    let num_indices = 6 * (size[0] - 1) as u64 * (size[1] - 1) as u64;
+    let indices_length = 4 * num_indices;
+    let vertices_length = 24 * num_vertices;
+    let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "mesh is too large for GLB");
+    if num_vertices > u32::MAX as u64 {
+        return Err(too_large());
+    }
+
+    let mut bounds = [[f32::INFINITY; 3], [f32::NEG_INFINITY; 3]];
+    for_each_strip(height_map, map_shape, min, max, config, |strip| {
+        for p in strip.buffer.positions[strip.vertices(strip.rows.clone())].iter() {
+            for i in 0..3 {
+                bounds[0][i] = bounds[0][i].min(p[i]);
+                bounds[1][i] = bounds[1][i].max(p[i]);
+            }
+        }
+        Ok(())
+    })?;
+    let [lo, hi] = bounds;
+
+    // The indices come first, since they only depend on the grid and can be written without meshing.
+    let mut json = format!(
+        "{{\"asset\":{{\"version\":\"2.0\",\"generator\":\"height-mesh\"}},\
+         \"scene\":0,\"scenes\":[{{\"nodes\":[0]}}],\"nodes\":[{{\"mesh\":0}}],\
+         \"meshes\":[{{\"primitives\":[{{\"attributes\":{{\"POSITION\":1,\"NORMAL\":2}},\"indices\":0,\"mode\":4}}]}}],\
+         \"accessors\":[\
+         {{\"bufferView\":0,\"componentType\":5125,\"count\":{},\"type\":\"SCALAR\"}},\
+         {{\"bufferView\":1,\"byteOffset\":0,\"componentType\":5126,\"count\":{},\"type\":\"VEC3\",\"min\":[{},{},{}],\"max\":[{},{},{}]}},\
+         {{\"bufferView\":1,\"byteOffset\":12,\"componentType\":5126,\"count\":{},\"type\":\"VEC3\"}}],\
+         \"bufferViews\":[\
+         {{\"buffer\":0,\"byteOffset\":0,\"byteLength\":{},\"target\":34963}},\
+         {{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"byteStride\":24,\"target\":34962}}],\
+         \"buffers\":[{{\"byteLength\":{}}}]}}",
+        num_indices,
+        num_vertices,
+        lo[0],
+        lo[1],
+        lo[2],
+        hi[0],
+        hi[1],
+        hi[2],
+        num_vertices,
+        indices_length,
+        indices_length,
+        vertices_length,
+        indices_length + vertices_length,
+    );
+    // Chunks must be 4-byte aligned, and the JSON chunk is padded with spaces.
+    while json.len() % 4 != 0 {
+        json.push(' ');
+    }
+    let bin_length = indices_length + vertices_length;
+    let total_length = 12 + 8 + json.len() as u64 + 8 + bin_length;
+    let total_length = u32::try_from(total_length).map_err(|_| too_large())?;
+
+    writer.write_all(b"glTF")?;
+    writer.write_all(&2u32.to_le_bytes())?;
+    writer.write_all(&total_length.to_le_bytes())?;
+    writer.write_all(&(json.len() as u32).to_le_bytes())?;
+    writer.write_all(b"JSON")?;
+    writer.write_all(json.as_bytes())?;
+    writer.write_all(&(bin_length as u32).to_le_bytes())?;
+    writer.write_all(b"BIN\0")?;
+
+    // The same triangles as the mesher's index pass, which numbers the vertices row by row.
+    for cz in 0..size[1] - 1 {
+        for cx in 0..size[0] - 1 {
+            let bl = cz * size[0] + cx;
+            let flipped = config.diagonals.is_flipped([imin[0] + cx, imin[1] + cz]);
+            let quad =
+                diagonals::quad_indices([bl, bl + 1, bl + size[0], bl + size[0] + 1], flipped);
+            for i in quad {
+                writer.write_all(&i.to_le_bytes())?;
+            }
+        }
+    }
+
+    for_each_strip(height_map, map_shape, min, max, config, |strip| {
+        for v in strip.vertices(strip.new_rows(imin[1])) {
+            let normal = normalize(strip.buffer.normals[v]);
+            for c in strip.buffer.positions[v].iter().chain(normal.iter()) {
+                writer.write_all(&c.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    })?;
+
+    writer.flush()
+}
+
+/// One strip of a streamed mesh.
+struct Strip<'a> {
+    /// The strip meshed on its own, padded with extra rows so that its normals match the full mesh.
+    buffer: &'a HeightMeshBuffer,
+    /// The number of vertices per row.
+    width: u32,
+    /// The rows of vertices in `buffer`.
+    mesh_rows: Range<u32>,
+    /// The rows of vertices that belong to this strip. Consecutive strips share a row, so the cells between them are
+    /// covered.
+    rows: Range<u32>,
+}
+
+impl Strip<'_> {
+    /// The strip's rows that the previous strip didn't have, given the first row of the whole mesh. The first row of each
+    /// strip after the first is the last row of the previous one.
+    fn new_rows(&self, first_row: u32) -> Range<u32> {
+        if self.rows.start == first_row {
+            self.rows.clone()
+        } else {
+            self.rows.start + 1..self.rows.end
+        }
+    }
+
+    /// The range of `buffer` vertices in `rows`.
+    fn vertices(&self, rows: Range<u32>) -> Range<usize> {
+        let row = |z: u32| ((z - self.mesh_rows.start) * self.width) as usize;
+        row(rows.start)..row(rows.end)
+    }
+
+    /// The indices of the cells between the strip's rows, relative to `buffer`. The mesher emits cells row by row, with two
+    /// triangles each.
+    fn triangles(&self) -> &[u32] {
+        let cells_per_row = 6 * self.width.saturating_sub(1) as usize;
+        let row = |z: u32| (z - self.mesh_rows.start) as usize * cells_per_row;
+        &self.buffer.indices[row(self.rows.start)..row(self.rows.end - 1)]
+    }
+}
+
+/// Meshes the interior rows of `[min, max]` in strips of [`STRIP_ROWS`], passing each one to `f` in order.
+///
+/// Each strip's samples are copied into a scratch map of their own, so the memory used doesn't grow with the size of the
+/// whole map.
+fn for_each_strip<M, S, F>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &HeightMeshConfig,
+    mut f: F,
+) -> io::Result<()>
+where
+    M: HeightMapView + ?Sized,
+    S: Shape<u32, 2>,
+    F: FnMut(&Strip) -> io::Result<()>,
+{
+    assert!(max[0] >= min[0] + 2 && max[1] >= min[1] + 2);
+
+    let strip_config = HeightMeshConfig {
+        shadow_caster_step: None,
+        lod_steps: &[],
+        uvs: None,
+        lightmap_uvs: None,
+        material_map: None,
+        triangle_cells: false,
+        texture_layer_map: None,
+        ambient_occlusion: None,
+        curvature: false,
+        line_indices: false,
+        max_edge_length: None,
+        // The strips' own borders must not be snapped, so this is done below for the borders of the whole mesh.
+        border_snap: None,
+        walkable_slope: None,
+        packed_normals: false,
+        content_hash: false,
+        ..config.clone()
+    };
+    let padding = config.normal_mode.footprint();
+    let width = max[0] - min[0] - 1;
+    let [first_row, last_row] = [min[1] + 1, max[1] - 1];
+
+    let mut samples = Vec::new();
+    let mut buffer = HeightMeshBuffer::default();
+    let mut start = first_row;
+    loop {
+        let end = (start + STRIP_ROWS).min(last_row);
+
+        // Normals near the edge of a mesh are computed differently, so mesh enough extra rows around the strip that its own
+        // vertices are far enough from the edges of the strip mesh.
+        let mesh_rows =
+            start.saturating_sub(padding).max(first_row)..(end + padding).min(last_row) + 1;
+        let origin = [min[0], mesh_rows.start - 1];
+        let size = [width + 2, mesh_rows.len() as u32 + 2];
+        samples.clear();
+        for z in origin[1]..origin[1] + size[1] {
+            for x in origin[0]..origin[0] + size[0] {
+                samples.push(height_map.height(map_shape.linearize([x, z]) as usize));
+            }
+        }
+        height_mesh_window(
+            &samples[..],
+            &Shape2u32::new(size),
+            origin,
+            [0; 2],
+            size.map(|s| s - 1),
+            &strip_config,
+            &mut buffer,
+        );
+        if let Some(lattice) = config.border_snap {
+            for (i, p) in buffer.positions.iter_mut().enumerate() {
+                let x = i as u32 % width;
+                let z = mesh_rows.start + i as u32 / width;
+                if x == 0 || x == width - 1 || z == first_row || z == last_row {
+                    *p = snap_to_lattice(*p, lattice);
+                }
+            }
+        }
+
+        f(&Strip {
+            buffer: &buffer,
+            width,
+            mesh_rows,
+            rows: start..end + 1,
+        })?;
+
+        if end == last_row {
+            return Ok(());
+        }
+        start = end;
+    }
+}
+
+pub(crate) fn write_stl_facet<W: Write>(writer: &mut W, vertices: [[f32; 3]; 3]) -> io::Result<()> {
+    let normal = normalize(cross(
+        sub(vertices[1], vertices[0]),
+        sub(vertices[2], vertices[0]),
+    ));
+    for c in normal.iter().chain(vertices.iter().flatten()) {
+        writer.write_all(&c.to_le_bytes())?;
+    }
+    // Attribute byte count, unused.
+    writer.write_all(&[0; 2])
+}
+
+/// Writes the mesh as a self-contained glTF 2.0 file (JSON with the binary buffer embedded as a base64 data URI).
+///
+/// Positions, `u32` indices, and (if present) normals and [`HeightMeshBuffer::uvs`] are written. glTF requires unit
+/// normals, so they are normalized (or unpacked) on the way out. If the buffer has [`HeightMeshBuffer::metadata`], it is stored in the `extras`
+/// of the mesh.
+pub fn write_gltf<W: Write>(buffer: &HeightMeshBuffer, mut writer: W) -> io::Result<()> {
+    let num_vertices = buffer.positions.len();
+    let has_normals = buffer.has_normals() && num_vertices > 0;
+    let has_uvs = buffer.uvs.len() == num_vertices && num_vertices > 0;
+
+    let mut bin = Vec::new();
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for p in buffer.positions.iter() {
+        for i in 0..3 {
+            min[i] = min[i].min(p[i]);
+            max[i] = max[i].max(p[i]);
+        }
+        bin.extend(p.iter().flat_map(|c| c.to_le_bytes()));
+    }
+    if num_vertices == 0 {
+        min = [0.0; 3];
+        max = [0.0; 3];
+    }
+    let normals_offset = bin.len();
+    if has_normals {
+        for i in 0..num_vertices {
+            let n = normalize(buffer.normal(i).unwrap());
+            bin.extend(n.iter().flat_map(|c| c.to_le_bytes()));
+        }
+    }
+    let uvs_offset = bin.len();
+    if has_uvs {
+        for uv in buffer.uvs.iter() {
+            bin.extend(uv.iter().flat_map(|c| c.to_le_bytes()));
+        }
+    }
+    let indices_offset = bin.len();
+    bin.extend(buffer.indices.iter().flat_map(|i| i.to_le_bytes()));
+
+    let vec3_size = 12 * num_vertices;
+    // Each accessor has its own buffer view, so they share indices.
+    let mut buffer_views = vec![
+        format!(
+            "{{\"buffer\":0,\"byteOffset\":0,\"byteLength\":{},\"target\":34962}}",
+            vec3_size
+        ),
+        format!(
+            "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"target\":34963}}",
+            indices_offset,
+            4 * buffer.indices.len()
+        ),
+    ];
+    let mut accessors = vec![
+        format!(
+            "{{\"bufferView\":0,\"componentType\":5126,\"count\":{},\"type\":\"VEC3\",\"min\":[{},{},{}],\"max\":[{},{},{}]}}",
+            num_vertices, min[0], min[1], min[2], max[0], max[1], max[2]
+        ),
+        format!(
+            "{{\"bufferView\":1,\"componentType\":5125,\"count\":{},\"type\":\"SCALAR\"}}",
+            buffer.indices.len()
+        ),
+    ];
+    let mut attributes = String::from("\"POSITION\":0");
+    if has_normals {
+        let view = buffer_views.len();
+        buffer_views.push(format!(
+            "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"target\":34962}}",
+            normals_offset, vec3_size
+        ));
+        accessors.push(format!(
+            "{{\"bufferView\":{},\"componentType\":5126,\"count\":{},\"type\":\"VEC3\"}}",
+            view, num_vertices
+        ));
+        attributes.push_str(&format!(",\"NORMAL\":{}", view));
+    }
+    if has_uvs {
+        let view = buffer_views.len();
+        buffer_views.push(format!(
+            "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"target\":34962}}",
+            uvs_offset,
+            8 * num_vertices
+        ));
+        accessors.push(format!(
+            "{{\"bufferView\":{},\"componentType\":5126,\"count\":{},\"type\":\"VEC2\"}}",
+            view, num_vertices
+        ));
+        attributes.push_str(&format!(",\"TEXCOORD_0\":{}", view));
+    }
+    let extras = match &buffer.metadata {
+        Some(metadata) => format!(",\"extras\":{}", metadata.to_gltf_extras()),
+        None => String::new(),
+    };
+
+    write!(
+        writer,
+        "{{\"asset\":{{\"version\":\"2.0\",\"generator\":\"height-mesh\"}},\
+         \"scene\":0,\"scenes\":[{{\"nodes\":[0]}}],\"nodes\":[{{\"mesh\":0}}],\
+         \"meshes\":[{{\"primitives\":[{{\"attributes\":{{{}}},\"indices\":1,\"mode\":4}}]{}}}],\
+         \"accessors\":[{}],\"bufferViews\":[{}],\
+         \"buffers\":[{{\"byteLength\":{},\"uri\":\"data:application/octet-stream;base64,{}\"}}]}}",
+        attributes,
+        extras,
+        accessors.join(","),
+        buffer_views.join(","),
+        bin.len(),
+        base64(&bin),
+    )?;
+
+    writer.flush()
+}
+
+/// Writes the vertices as a binary little-endian PLY point cloud, without any faces, for surface reconstruction and
+/// registration tools.
+///
+/// Each point has its position, plus its normalized normal if the buffer has normals (packed or not) and an 8-bit RGB color
+/// if [`HeightMeshBuffer::colors`] is present.
+/// If the buffer has [`HeightMeshBuffer::metadata`], it is recorded as comments in the header.
+pub fn write_ply_points<W: Write>(buffer: &HeightMeshBuffer, mut writer: W) -> io::Result<()> {
+    let has_normals = buffer.has_normals();
+    let has_colors = !buffer.colors.is_empty();
+
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format binary_little_endian 1.0")?;
+    if let Some(metadata) = &buffer.metadata {
+        for line in metadata.comment_lines() {
+            writeln!(writer, "comment {}", line)?;
+        }
+    }
+    writeln!(writer, "element vertex {}", buffer.positions.len())?;
+    for property in ["x", "y", "z"] {
+        writeln!(writer, "property float {}", property)?;
+    }
+    if has_normals {
+        for property in ["nx", "ny", "nz"] {
+            writeln!(writer, "property float {}", property)?;
+        }
+    }
+    if has_colors {
+        for property in ["red", "green", "blue"] {
+            writeln!(writer, "property uchar {}", property)?;
+        }
+    }
+    writeln!(writer, "end_header")?;
+
+    for (i, p) in buffer.positions.iter().enumerate() {
+        for c in p.iter() {
+            writer.write_all(&c.to_le_bytes())?;
+        }
+        if has_normals {
+            for c in normalize(buffer.normal(i).unwrap()).iter() {
+                writer.write_all(&c.to_le_bytes())?;
+            }
+        }
+        if has_colors {
+            let [r, g, b, _] = buffer.colors[i];
+            writer.write_all(&[r, g, b].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8))?;
+        }
+    }
+
+    writer.flush()
+}
+
+/// Writes the vertices as an XYZ point cloud: one `x y z nx ny nz` line per point, with normalized normals, or one
+/// `x y z` line per point if the buffer has no normals.
+///
+/// `writer` should be buffered, since this performs many small writes.
+pub fn write_xyz<W: Write>(buffer: &HeightMeshBuffer, mut writer: W) -> io::Result<()> {
+    let has_normals = buffer.has_normals();
+    for (i, p) in buffer.positions.iter().enumerate() {
+        match buffer.normal(i).filter(|_| has_normals) {
+            Some(n) => {
+                let n = normalize(n);
+                writeln!(
+                    writer,
+                    "{} {} {} {} {} {}",
+                    p[0], p[1], p[2], n[0], n[1], n[2]
+                )?;
+            }
+            None => writeln!(writer, "{} {} {}", p[0], p[1], p[2])?,
+        }
+    }
+
+    writer.flush()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(4 * bytes.len().div_ceil(3));
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{height_mesh_with_config, DiagonalPattern};
+    use ndshape::Shape2u32;
+
+    #[test]
+    fn streamed_glb_matches_full_mesh() {
+        // Tall enough for several strips.
+        let rows = 2 * STRIP_ROWS + 20;
+        let shape = Shape2u32::new([12, rows]);
+        let heights: Vec<f32> = (0..shape.size())
+            .map(|i| ((i % 12) as f32 * 0.7).sin() + (i / 12) as f32 * 0.1)
+            .collect();
+        let config = HeightMeshConfig {
+            diagonals: DiagonalPattern::Alternating,
+            ..Default::default()
+        };
+        let [min, max] = [[1, 2], [10, rows - 3]];
+        let mut buffer = HeightMeshBuffer::default();
+        height_mesh_with_config(&heights, &shape, min, max, &config, &mut buffer);
+
+        let mut glb = Vec::new();
+        write_glb_streaming(&heights, &shape, min, max, &config, &mut glb).unwrap();
+
+        let u32_at = |i: usize| u32::from_le_bytes(glb[i..i + 4].try_into().unwrap());
+        assert_eq!(&glb[..4], b"glTF");
+        assert_eq!(u32_at(8) as usize, glb.len());
+        let json_length = u32_at(12) as usize;
+        assert_eq!(&glb[16..20], b"JSON");
+        let bin_start = 20 + json_length + 8;
+        assert_eq!(&glb[bin_start - 4..bin_start], b"BIN\0");
+        let bin = &glb[bin_start..];
+
+        let words: Vec<[u8; 4]> = bin.chunks_exact(4).map(|c| c.try_into().unwrap()).collect();
+        let (indices, vertices) = words.split_at(buffer.indices.len());
+        let indices: Vec<u32> = indices.iter().map(|&w| u32::from_le_bytes(w)).collect();
+        assert_eq!(indices, buffer.indices);
+        let floats: Vec<f32> = vertices.iter().map(|&w| f32::from_le_bytes(w)).collect();
+        assert_eq!(floats.len(), 6 * buffer.positions.len());
+        for (v, chunk) in floats.chunks_exact(6).enumerate() {
+            assert_eq!(chunk[..3], buffer.positions[v]);
+            assert_eq!(chunk[3..], normalize(buffer.normals[v]));
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::HeightMeshBuffer;
+
+use std::ops::Range;
+
+impl HeightMeshBuffer {
+    /// Copies the triangles in `triangles` (counted in triangles, not indices) into a new buffer, keeping only the vertices
+    /// they use, so part of a mesh can be exported or re-rendered on its own.
+    ///
+    /// Vertices are renumbered in the order they are first used. Every per-vertex channel that is present is copied, along
+    /// with the triangle materials, triangle cells, and metadata. Line, LOD, and shadow caster indices describe the whole mesh, so they
+    /// are not copied, and the patch has no `stride_to_index` mapping.
+    pub fn extract_triangles(&self, triangles: Range<usize>) -> HeightMeshBuffer {
+        self.extract(triangles)
+    }
+
+    /// Copies the cells (quads) whose bottom-left corner sample lies in `[cell_min, cell_max]` into a new buffer, like
+    /// [`HeightMeshBuffer::extract_triangles`].
+    ///
+    /// `mesh_min` and `mesh_max` must be the `min` and `max` that this mesh was generated with, and the cells are clamped
+    /// to the ones that it has. This relies on the mesher's triangle order, so it doesn't work after
+    /// [`HeightMeshConfig::max_edge_length`](crate::HeightMeshConfig::max_edge_length) subdivision or other edits to the
+    /// indices.
+    pub fn extract_cells(
+        &self,
+        mesh_min: [u32; 2],
+        mesh_max: [u32; 2],
+        cell_min: [u32; 2],
+        cell_max: [u32; 2],
+    ) -> HeightMeshBuffer {
+        // Quads have their bottom-left corner in [min + 1, max - 2].
+        let first = [mesh_min[0] + 1, mesh_min[1] + 1];
+        let last = [mesh_max[0].saturating_sub(2), mesh_max[1].saturating_sub(2)];
+        let cells_x = (last[0] + 1).saturating_sub(first[0]) as usize;
+
+        let x0 = cell_min[0].max(first[0]);
+        let x1 = cell_max[0].min(last[0]);
+        let z0 = cell_min[1].max(first[1]);
+        let z1 = cell_max[1].min(last[1]);
+        if x0 > x1 {
+            return self.extract(std::iter::empty());
+        }
+
+        // Each row of cells is a contiguous run of triangles.
+        let rows = (z0..=z1).flat_map(|z| {
+            let row_start = (z - first[1]) as usize * cells_x;
+            let start = 2 * (row_start + (x0 - first[0]) as usize);
+            let end = 2 * (row_start + (x1 - first[0]) as usize + 1);
+            start..end
+        });
+        self.extract(rows)
+    }
+
+    fn extract(&self, triangles: impl IntoIterator<Item = usize>) -> HeightMeshBuffer {
+        let mut patch = HeightMeshBuffer {
+            metadata: self.metadata.clone(),
+            ..Default::default()
+        };
+        let num_vertices = self.positions.len();
+        let has_materials = self.triangle_materials.len() * 3 == self.indices.len();
+        let has_cells = self.triangle_cells.len() * 3 == self.indices.len();
+
+        let mut local_index = vec![u32::MAX; num_vertices];
+        let mut source_vertices = Vec::new();
+        for t in triangles {
+            for &v in &self.indices[3 * t..3 * t + 3] {
+                let local = &mut local_index[v as usize];
+                if *local == u32::MAX {
+                    *local = source_vertices.len() as u32;
+                    source_vertices.push(v as usize);
+                }
+                patch.indices.push(*local);
+            }
+            if has_materials {
+                patch.triangle_materials.push(self.triangle_materials[t]);
+            }
+            if has_cells {
+                patch.triangle_cells.push(self.triangle_cells[t]);
+            }
+        }
+
+        gather(&mut patch.positions, &self.positions, &source_vertices);
+        gather(&mut patch.normals, &self.normals, &source_vertices);
+        gather(
+            &mut patch.packed_normals,
+            &self.packed_normals,
+            &source_vertices,
+        );
+        gather(&mut patch.uvs, &self.uvs, &source_vertices);
+        gather(
+            &mut patch.lightmap_uvs,
+            &self.lightmap_uvs,
+            &source_vertices,
+        );
+        gather(&mut patch.colors, &self.colors, &source_vertices);
+        gather(&mut patch.curvature, &self.curvature, &source_vertices);
+        gather(
+            &mut patch.ambient_occlusion,
+            &self.ambient_occlusion,
+            &source_vertices,
+        );
+        gather(
+            &mut patch.texture_layers,
+            &self.texture_layers,
+            &source_vertices,
+        );
+        gather(
+            &mut patch.texture_layer_weights,
+            &self.texture_layer_weights,
+            &source_vertices,
+        );
+
+        patch
+    }
+}
+
+fn gather<T: Copy>(dst: &mut Vec<T>, src: &[T], source_vertices: &[usize]) {
+    if !src.is_empty() {
+        dst.extend(source_vertices.iter().map(|&v| src[v]));
+    }
+}
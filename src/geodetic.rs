@@ -0,0 +1,59 @@
+/// Places the samples of a height map on a regular latitude/longitude grid, like an SRTM or other geographic DEM tile. See
+/// [`HeightMeshConfig::geodetic`](crate::HeightMeshConfig::geodetic).
+///
+/// A degree of longitude shrinks toward the poles, so meshing such a tile with uniform spacing squashes it along Y and
+/// stretches it along X. With this set, the mesher instead computes the spacing of each row from its latitude, and vertex
+/// positions are in meters relative to sample `[0, 0]`. The heights should also be in meters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GeodeticSpacing {
+    /// The `[longitude, latitude]` of sample `[0, 0]`, in degrees.
+    pub origin: [f64; 2],
+    /// The `[longitude, latitude]` distance between adjacent samples, in degrees. Latitude increases along +Z.
+    pub sample_spacing: [f64; 2],
+}
+
+impl GeodeticSpacing {
+    /// The spacing for a map of `size` samples whose first and last samples lie on the edges of the extent, as in SRTM
+    /// tiles where neighboring tiles share their edge rows and columns.
+    pub fn from_extent(west: f64, south: f64, east: f64, north: f64, size: [u32; 2]) -> Self {
+        let cells_x = size[0].saturating_sub(1).max(1) as f64;
+        let cells_y = size[1].saturating_sub(1).max(1) as f64;
+        Self {
+            origin: [west, south],
+            sample_spacing: [(east - west) / cells_x, (north - south) / cells_y],
+        }
+    }
+
+    /// The latitude of row `z`, in degrees.
+    pub fn latitude(&self, z: u32) -> f64 {
+        self.origin[1] + z as f64 * self.sample_spacing[1]
+    }
+
+    /// The `[x, z]` distance in meters between adjacent samples on row `z`.
+    pub fn meters_per_sample(&self, z: u32) -> [f64; 2] {
+        let [lon, lat] = meters_per_degree(self.latitude(z));
+        [lon * self.sample_spacing[0], lat * self.sample_spacing[1]]
+    }
+
+    /// The `[x, z]` position in meters of sample `[x, z]`, relative to sample `[0, 0]`.
+    ///
+    /// X is measured along the row at the row's latitude, so the columns converge toward the poles like meridians do.
+    pub fn position(&self, [x, z]: [u32; 2]) -> [f64; 2] {
+        let [lon_meters, _] = meters_per_degree(self.latitude(z));
+        // Meridian arc length, using the scale at the midpoint latitude.
+        let [_, lat_meters] = meters_per_degree(0.5 * (self.origin[1] + self.latitude(z)));
+        [
+            x as f64 * self.sample_spacing[0] * lon_meters,
+            z as f64 * self.sample_spacing[1] * lat_meters,
+        ]
+    }
+}
+
+/// The length in meters of one degree of `[longitude, latitude]` at `latitude` (in degrees) on the WGS 84 ellipsoid.
+pub fn meters_per_degree(latitude: f64) -> [f64; 2] {
+    let phi = latitude.to_radians();
+    let lon = 111_412.84 * phi.cos() - 93.5 * (3.0 * phi).cos() + 0.118 * (5.0 * phi).cos();
+    let lat = 111_132.92 - 559.82 * (2.0 * phi).cos() + 1.175 * (4.0 * phi).cos()
+        - 0.0023 * (6.0 * phi).cos();
+    [lon, lat]
+}
@@ -0,0 +1,175 @@
+use crate::{DiagonalPattern, HeightMapView, HeightMeshConfig, NormalMode, UvMode};
+
+use ndshape::Shape;
+
+/// A stable 64-bit hash of everything that determines the output of
+/// [`height_mesh_with_config`](crate::height_mesh_with_config): the region, the samples in `[min, max]` (including the
+/// boundary), and every field of `config` (other than [`HeightMeshConfig::content_hash`] itself), including the material
+/// and texture layer map samples.
+///
+/// Unlike `std::hash`, the result never changes between runs, platforms, or compiler versions, so it can key on-disk caches
+/// and dedupe identical tiles. The mesher records it in
+/// [`HeightMeshBuffer::content_hash`](crate::HeightMeshBuffer::content_hash) when
+/// [`HeightMeshConfig::content_hash`] is set, so a cached mesh can be checked against new inputs without remeshing.
+pub fn content_hash<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &HeightMeshConfig,
+) -> u64 {
+    let mut h = Fnv1a::new();
+
+    h.u32s(&min);
+    h.u32s(&max);
+    for z in min[1]..=max[1] {
+        for x in min[0]..=max[0] {
+            let stride = map_shape.linearize([x, z]) as usize;
+            h.f32(height_map.height(stride));
+            if let Some(material_map) = config.material_map {
+                h.bytes(&[material_map[stride]]);
+            }
+            if let Some(layer_map) = config.texture_layer_map {
+                h.bytes(&[layer_map[stride]]);
+            }
+        }
+    }
+
+    let HeightMeshConfig {
+        vertical_scale,
+        height_remap,
+        detail,
+        geodetic,
+        normal_mode,
+        shadow_caster_step,
+        lod_steps,
+        uvs,
+        lightmap_uvs,
+        material_map,
+        triangle_cells,
+        texture_layer_map,
+        ambient_occlusion,
+        curvature,
+        line_indices,
+        max_edge_length,
+        border_snap,
+        walkable_slope,
+        packed_normals,
+        diagonals,
+        content_hash: _,
+    } = config;
+
+    h.f32(*vertical_scale);
+    h.option(height_remap.as_ref(), |h, r| {
+        h.f32s(&r.input);
+        h.f32s(&r.output);
+    });
+    h.option(detail.as_ref(), |h, d| {
+        h.u32s(&d.size);
+        h.f32(d.weight);
+        h.u32s(&d.offset);
+        h.f32s(d.heights);
+    });
+    h.option(geodetic.as_ref(), |h, g| {
+        h.f64s(&g.origin);
+        h.f64s(&g.sample_spacing);
+    });
+    match normal_mode {
+        NormalMode::CentralDifference => h.u32(0),
+        NormalMode::Smoothed { radius } => h.u32s(&[1, *radius]),
+        NormalMode::Sobel => h.u32(2),
+    }
+    h.option(shadow_caster_step.as_ref(), |h, s| h.u32(*s));
+    h.u32(lod_steps.len() as u32);
+    h.u32s(lod_steps);
+    h.option(uvs.as_ref(), |h, mode| match *mode {
+        UvMode::Normalized => h.u32(0),
+        UvMode::WorldSpace { scale } => {
+            h.u32(1);
+            h.f32(scale);
+        }
+        UvMode::Atlas { tile, tiles, inset } => {
+            h.u32(2);
+            h.u32s(&tile);
+            h.u32s(&tiles);
+            h.f32(inset);
+        }
+    });
+    h.option(lightmap_uvs.as_ref(), |h, l| {
+        h.u32s(&l.resolution);
+        h.u32(l.padding);
+    });
+    // The samples were hashed above.
+    h.bytes(&[
+        material_map.is_some() as u8,
+        texture_layer_map.is_some() as u8,
+    ]);
+    h.option(ambient_occlusion.as_ref(), |h, ao| {
+        h.u32(ao.radius);
+        h.f32(ao.strength);
+    });
+    h.bytes(&[*triangle_cells as u8, *curvature as u8, *line_indices as u8]);
+    h.option(max_edge_length.as_ref(), |h, l| h.f32(*l));
+    h.option(border_snap.as_ref(), |h, l| h.f32(*l));
+    h.option(walkable_slope.as_ref(), |h, s| h.f32(*s));
+    h.bytes(&[*packed_normals as u8]);
+    match diagonals {
+        DiagonalPattern::Uniform => h.bytes(&[0]),
+        DiagonalPattern::Alternating => h.bytes(&[1]),
+        DiagonalPattern::Seeded(seed) => {
+            h.bytes(&[2]);
+            h.bytes(&seed.to_le_bytes());
+        }
+    }
+
+    h.finish()
+}
+
+/// 64-bit FNV-1a, which is simple and fully specified.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn u32s(&mut self, vs: &[u32]) {
+        vs.iter().for_each(|&v| self.u32(v));
+    }
+
+    fn f32(&mut self, v: f32) {
+        self.u32(v.to_bits());
+    }
+
+    fn f32s(&mut self, vs: &[f32]) {
+        vs.iter().for_each(|&v| self.f32(v));
+    }
+
+    fn f64s(&mut self, vs: &[f64]) {
+        vs.iter()
+            .for_each(|&v| self.bytes(&v.to_bits().to_le_bytes()));
+    }
+
+    /// Hashes whether `value` is present, then the value itself.
+    fn option<T>(&mut self, value: Option<&T>, f: impl FnOnce(&mut Self, &T)) {
+        self.bytes(&[value.is_some() as u8]);
+        if let Some(value) = value {
+            f(self, value);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
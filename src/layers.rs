@@ -0,0 +1,24 @@
+use crate::{height_mesh_with_config, HeightMapView, HeightMeshBuffer, HeightMeshConfig};
+
+use ndshape::Shape;
+
+/// Meshes several co-registered height layers of the same shape (like terrain, water table, and bedrock) over the same
+/// `[min, max]`, writing one mesh per layer to `outputs`.
+///
+/// `outputs` is resized to the number of layers, reusing the buffers it already has. Every layer is meshed with the same
+/// `config`, so the meshes share the XZ grid and have identical vertex layouts and indices, and layers can be compared
+/// vertex by vertex or drawn with one index buffer. The exception is [`HeightMeshConfig::max_edge_length`], which
+/// subdivides each layer according to its own heights.
+pub fn height_mesh_layers<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    layers: &[&M],
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &HeightMeshConfig,
+    outputs: &mut Vec<HeightMeshBuffer>,
+) {
+    outputs.resize_with(layers.len(), Default::default);
+    for (layer, output) in layers.iter().zip(outputs.iter_mut()) {
+        height_mesh_with_config(*layer, map_shape, min, max, config, output);
+    }
+}
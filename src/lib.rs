@@ -25,9 +25,93 @@
 //! assert!(!buffer.indices.is_empty());
 //! ```
 
+pub mod encoding;
+pub mod export;
+pub mod mercator;
+pub mod presets;
+pub mod raw16;
+
+mod ao;
+mod append;
+mod boundary;
+mod costmap;
+mod decal;
+mod decimate;
+mod detail;
+mod diagonals;
+mod difference;
+mod displacement;
+mod downsample;
+mod extract;
+mod geodetic;
+mod hash;
+mod layers;
+mod materials;
+mod metadata;
+mod mirror;
+mod normals;
+mod occluder;
+mod pool;
+mod profiling;
+mod raycast;
+mod sample;
+mod seams;
+mod shadow_mask;
+mod shape;
+mod soup;
+mod split;
+mod stats;
+#[cfg(feature = "const-tables")]
+mod tables;
+mod tessellate;
+mod texture_layers;
+mod update;
+mod uvs;
+mod view;
+mod volume;
+
+pub use ao::AmbientOcclusionConfig;
+pub use append::SeamIndex;
+pub use costmap::*;
+pub use decal::*;
+pub use decimate::decimated_height_mesh;
+pub use detail::DetailMap;
+pub use diagonals::DiagonalPattern;
+pub use difference::*;
+pub use displacement::*;
+pub use downsample::*;
+pub use geodetic::*;
+pub use hash::content_hash;
+pub use layers::height_mesh_layers;
+pub use materials::MaterialRange;
+pub use metadata::SpatialMetadata;
+pub use mirror::MirrorAxis;
+pub use normals::{NormalMode, NormalWeighting};
+pub use occluder::*;
+pub use pool::BufferPool;
+#[cfg(feature = "profiling")]
+pub use profiling::MeshTimings;
+pub use raycast::RayHit;
+pub use sample::{elevation_profile, sample_surface, SurfaceSample};
+pub use seams::*;
+pub use shadow_mask::*;
+pub use shape::{padded, PaddedShape, RuntimeShape2};
+pub use soup::{triangle_soup, TriangleSoup};
+pub use split::U16Submesh;
+pub use stats::*;
+#[cfg(feature = "const-tables")]
+pub use tables::*;
+pub use update::update_height_mesh_region;
+pub use uvs::{LightmapUvConfig, UvMode};
+pub use view::*;
+pub use volume::*;
+
 pub use ndshape;
 
+use detail::Composited;
 use ndshape::Shape;
+use profiling::{Stage, Stopwatch};
+use std::ops::Range;
 
 /// The output buffers used by [`height_mesh`]. These buffers can be reused to avoid reallocating memory.
 #[derive(Default)]
@@ -38,10 +122,60 @@ pub struct HeightMeshBuffer {
     ///
     /// The normals are **not** normalized, since that is done most efficiently on the GPU.
     pub normals: Vec<[f32; 3]>,
+    /// Unit normals packed as signed normalized bytes (`x`, `y`, `z`, and a zero pad), written instead of `normals` when
+    /// [`HeightMeshConfig::packed_normals`] is set. Use [`HeightMeshBuffer::normal`] to read either kind.
+    pub packed_normals: Vec<[i8; 4]>,
     /// Triangle indices, referring to offsets in the `positions` and `normals` vectors.
     pub indices: Vec<u32>,
     /// Used to map back from pixel stride to vertex index.
     pub stride_to_index: Vec<u32>,
+    /// The material ID of each triangle, only generated when [`HeightMeshConfig::material_map`] is set.
+    pub triangle_materials: Vec<u8>,
+    /// The map stride of each triangle's cell (its bottom-left corner sample), only generated when
+    /// [`HeightMeshConfig::triangle_cells`] is set. See [`HeightMeshBuffer::triangle_data`].
+    pub triangle_cells: Vec<u32>,
+    /// Texture coordinates, only generated when [`HeightMeshConfig::uvs`] is set or by [`displacement_grid`].
+    pub uvs: Vec<[f32; 2]>,
+    /// Per-vertex RGBA colors, only generated by visualization utilities like [`height_difference_mesh`].
+    pub colors: Vec<[f32; 4]>,
+    /// Line list indices covering each unique edge of the mesh exactly once, only generated when
+    /// [`HeightMeshConfig::line_indices`] is set.
+    pub line_indices: Vec<u32>,
+    /// Per-vertex curvature, only generated when [`HeightMeshConfig::curvature`] is set.
+    pub curvature: Vec<f32>,
+    /// A per-vertex ambient occlusion term in `[0, 1]`, where 1 is unoccluded. Only generated when
+    /// [`HeightMeshConfig::ambient_occlusion`] is set.
+    pub ambient_occlusion: Vec<f32>,
+    /// The primary and secondary texture array layers of each vertex, only generated when
+    /// [`HeightMeshConfig::texture_layer_map`] is set.
+    pub texture_layers: Vec<[u8; 2]>,
+    /// How much of the secondary layer in `texture_layers` to blend into the primary one, in `[0, 0.5]`. Only generated
+    /// when [`HeightMeshConfig::texture_layer_map`] is set.
+    pub texture_layer_weights: Vec<f32>,
+    /// A second UV set for baked lighting, only generated when [`HeightMeshConfig::lightmap_uvs`] is set.
+    pub lightmap_uvs: Vec<[f32; 2]>,
+    /// Describes the real-world scale of the mesh for exporters. This is never written by the mesher, and it is kept by
+    /// [`HeightMeshBuffer::reset`], since it usually describes the height map rather than one particular mesh.
+    pub metadata: Option<SpatialMetadata>,
+    /// The coarse shadow caster mesh, only generated when [`HeightMeshConfig::shadow_caster_step`] is set.
+    pub shadow_caster: CoarseMeshBuffer,
+    /// One triangle index buffer per entry of [`HeightMeshConfig::lod_steps`], all referring to the same vertices as
+    /// `indices`.
+    pub lod_indices: Vec<Vec<u32>>,
+    /// The [`content_hash`] of the inputs that produced this mesh, written by [`height_mesh_with_config`] and its variants
+    /// when [`HeightMeshConfig::content_hash`] is set, and 0 otherwise. Compare it with the hash of new inputs to skip
+    /// remeshing a chunk that hasn't changed.
+    pub content_hash: u64,
+    /// The triangles (as in `indices`) no steeper than [`HeightMeshConfig::walkable_slope`], only generated when it is set.
+    pub walkable_indices: Vec<u32>,
+    /// The triangles (as in `indices`) steeper than [`HeightMeshConfig::walkable_slope`], only generated when it is set.
+    pub steep_indices: Vec<u32>,
+    /// The boundary vertices welded by [`HeightMeshBuffer::append`], kept so that appending many chunks doesn't rescan
+    /// the whole mesh each time.
+    pub seam_index: SeamIndex,
+    /// How long each stage of the mesher took, written by [`height_mesh_with_config`] and its variants.
+    #[cfg(feature = "profiling")]
+    pub timings: MeshTimings,
 }
 
 impl HeightMeshBuffer {
@@ -49,13 +183,183 @@ impl HeightMeshBuffer {
     pub fn reset(&mut self, array_size: usize) {
         self.positions.clear();
         self.normals.clear();
+        self.packed_normals.clear();
         self.indices.clear();
+        self.uvs.clear();
+        self.colors.clear();
+        self.line_indices.clear();
+        self.triangle_materials.clear();
+        self.triangle_cells.clear();
+        self.curvature.clear();
+        self.ambient_occlusion.clear();
+        self.texture_layers.clear();
+        self.texture_layer_weights.clear();
+        self.lightmap_uvs.clear();
+        self.shadow_caster.reset();
+        self.walkable_indices.clear();
+        self.steep_indices.clear();
+        self.seam_index.clear();
+        self.content_hash = 0;
+        for indices in self.lod_indices.iter_mut() {
+            indices.clear();
+        }
 
         // Just make sure this buffer is long enough, whether or not we've used it before.
         self.stride_to_index.resize(array_size, 0);
     }
 }
 
+/// A positions-only mesh, used for coarse proxy geometry like occluders that never needs shading attributes.
+#[derive(Default)]
+pub struct CoarseMeshBuffer {
+    /// The vertex positions.
+    pub positions: Vec<[f32; 3]>,
+    /// Triangle indices, referring to offsets in the `positions` vector.
+    pub indices: Vec<u32>,
+}
+
+impl CoarseMeshBuffer {
+    /// Clears all of the buffers, but keeps the memory allocated for reuse.
+    pub fn reset(&mut self) {
+        self.positions.clear();
+        self.indices.clear();
+    }
+}
+
+/// Optional outputs and adjustments for [`height_mesh_with_config`].
+///
+/// The default configuration generates exactly the same mesh as [`height_mesh`].
+#[derive(Clone, Debug)]
+pub struct HeightMeshConfig<'a> {
+    /// Multiplies every height before it is used, for the vertical exaggeration common in GIS visualization.
+    ///
+    /// Unlike scaling the finished mesh along Y, this keeps the normals consistent with the exaggerated positions, since
+    /// the gradients are computed from the scaled heights.
+    pub vertical_scale: f32,
+    /// When set, every height is clamped and remapped before it is used (and before `vertical_scale`), so out-of-range
+    /// sensor data can be meshed without a preprocessing copy of the map. Positions, normals, and every other derived
+    /// channel see the remapped heights.
+    pub height_remap: Option<HeightRemap>,
+    /// When set, a tiled detail map is added to every height before it is used (and before `height_remap`), so fine
+    /// surface detail doesn't require a precomputed combined map for every chunk.
+    pub detail: Option<DetailMap<'a>>,
+    /// When set, the height map is treated as a latitude/longitude grid and vertex positions and normals are in meters,
+    /// with the X spacing of each row computed from its latitude.
+    ///
+    /// Curvature, ambient occlusion, and UVs are still computed in sample units.
+    pub geodetic: Option<GeodeticSpacing>,
+    /// How vertex normals are estimated from the height map.
+    pub normal_mode: NormalMode,
+    /// When set, a second positions-only mesh with a vertex every `shadow_caster_step` samples is written to
+    /// [`HeightMeshBuffer::shadow_caster`].
+    ///
+    /// This is intended for rendering shadow maps, where the full-resolution mesh is wasted effort. It reuses the vertex
+    /// positions of the main mesh, so it covers the same footprint and costs little more than the extra indices.
+    pub shadow_caster_step: Option<u32>,
+    /// For each step in this list, an extra index buffer that only uses every `step`-th row and column of vertices is
+    /// written to [`HeightMeshBuffer::lod_indices`].
+    ///
+    /// These coarser levels of detail share the full-resolution vertex buffer, so switching between them by distance is
+    /// just a matter of binding a different index buffer. The last row and column are always included, so each level covers
+    /// the same footprint. Neighboring chunks drawn at different levels will have T-junctions along their shared border.
+    pub lod_steps: &'a [u32],
+    /// When set, texture coordinates are written to [`HeightMeshBuffer::uvs`].
+    pub uvs: Option<UvMode>,
+    /// When set, a unique, non-overlapping UV set suitable for lightmap baking is written to
+    /// [`HeightMeshBuffer::lightmap_uvs`].
+    pub lightmap_uvs: Option<LightmapUvConfig>,
+    /// A per-cell material (or biome) ID map with the same shape as the height map. When set, the ID of each triangle is
+    /// written to [`HeightMeshBuffer::triangle_materials`].
+    ///
+    /// A cell is identified by its bottom-left (minimum) corner sample, so the IDs on the top and right edges of the
+    /// interior are never used. Use [`HeightMeshBuffer::split_by_material`] to group the triangles into submeshes.
+    pub material_map: Option<&'a [u8]>,
+    /// When set, the cell of each triangle is written to [`HeightMeshBuffer::triangle_cells`], so that arbitrary per-cell
+    /// data (like province IDs or navigation flags) can be looked up per triangle with [`HeightMeshBuffer::triangle_data`].
+    pub triangle_cells: bool,
+    /// A per-cell texture array layer (or terrain type) map with the same shape as the height map, identified by the
+    /// bottom-left corner sample like the material map. When set, the most common layer of the four cells around each
+    /// vertex is written to [`HeightMeshBuffer::texture_layers`] along with the second most common, and the weight of the
+    /// second to [`HeightMeshBuffer::texture_layer_weights`], so terrain can be shaded from a texture array in a single
+    /// draw call without a splatmap.
+    ///
+    /// The cells on the left and bottom of the interior are read too, so chunks padded with their neighbors' samples agree
+    /// on their shared vertices.
+    pub texture_layer_map: Option<&'a [u8]>,
+    /// When set, a cheap horizon-based ambient occlusion term is computed for each vertex and written to
+    /// [`HeightMeshBuffer::ambient_occlusion`].
+    ///
+    /// The horizon search may read any sample in `[min, max]`, including the boundary, so larger chunk padding gives more
+    /// consistent results across chunk borders.
+    pub ambient_occlusion: Option<AmbientOcclusionConfig>,
+    /// When true, the curvature at each vertex is written to [`HeightMeshBuffer::curvature`].
+    ///
+    /// This is the Laplacian of the height map from second differences: positive in cavities and valleys, negative on
+    /// ridges and peaks, and zero on planar slopes. Terrain shaders use it for cavity darkening and for deciding where snow
+    /// or sediment accumulates.
+    pub curvature: bool,
+    /// When true, a line list index buffer of all unique mesh edges (including the triangle diagonals) is written to
+    /// [`HeightMeshBuffer::line_indices`], for drawing wireframe overlays without the renderer's global wireframe mode.
+    pub line_indices: bool,
+    /// When set, cells are subdivided until no edge along X or Z is longer than this (in the units of the positions), so
+    /// that triangles on very steep terrain keep reasonable aspect ratios for physics and shading.
+    ///
+    /// Whole columns and rows of cells are subdivided together to avoid T-junctions, and the new vertices interpolate
+    /// every per-vertex channel bilinearly. The line, LOD, and shadow caster index buffers still cover only the original
+    /// vertices.
+    pub max_edge_length: Option<f32>,
+    /// When set, every coordinate of the vertices on the border of the mesh is rounded to the nearest multiple of this
+    /// lattice spacing.
+    ///
+    /// Adjacent chunks that share their border samples already produce the same border vertices, but only if the
+    /// arithmetic that produced them was identical. Snapping makes the border bit-exact regardless (e.g. with geodetic
+    /// spacing, or when props are authored against the same lattice). Border normals are computed from the shared samples
+    /// and are not snapped, so chunks padded with their neighbors' samples agree on them as well.
+    pub border_snap: Option<f32>,
+    /// When set, the triangles are also partitioned by this slope threshold (in radians from horizontal) into
+    /// [`HeightMeshBuffer::walkable_indices`] and [`HeightMeshBuffer::steep_indices`], so renderers can use different
+    /// materials for cliffs and physics can build cheaper colliders from only the walkable set.
+    pub walkable_slope: Option<f32>,
+    /// When set, the normals are normalized and written to [`HeightMeshBuffer::packed_normals`] as 4 bytes each, and
+    /// [`HeightMeshBuffer::normals`] is left empty. This cuts the memory for normals to a third, for platforms where vertex
+    /// memory is tight. The byte layout matches GPU vertex formats like `Snorm8x4`.
+    pub packed_normals: bool,
+    /// Which diagonal splits each quad of the full-resolution mesh. The coarse LOD and shadow caster meshes always use the
+    /// uniform split.
+    pub diagonals: DiagonalPattern,
+    /// When set, the [`content_hash`] of the inputs is recorded in [`HeightMeshBuffer::content_hash`]. Hashing reads every
+    /// sample again, so it is off by default.
+    pub content_hash: bool,
+}
+
+impl Default for HeightMeshConfig<'_> {
+    fn default() -> Self {
+        Self {
+            vertical_scale: 1.0,
+            height_remap: None,
+            detail: None,
+            geodetic: None,
+            normal_mode: NormalMode::CentralDifference,
+            shadow_caster_step: None,
+            lod_steps: &[],
+            uvs: None,
+            lightmap_uvs: None,
+            material_map: None,
+            triangle_cells: false,
+            texture_layer_map: None,
+            curvature: false,
+            ambient_occlusion: None,
+            line_indices: false,
+            max_edge_length: None,
+            border_snap: None,
+            walkable_slope: None,
+            packed_normals: false,
+            diagonals: DiagonalPattern::Uniform,
+            content_hash: false,
+        }
+    }
+}
+
 /// Generates a mesh with a vertex at each point on the interior of `[min, max]`.
 ///
 /// The generated vertices are of the form `[x, height, z]` where `height` is taken directly from `height_map`.
@@ -75,19 +379,137 @@ impl HeightMeshBuffer {
 ///
 /// b   b   b   b
 /// ```
-pub fn height_mesh<S: Shape<u32, 2>>(
-    height_map: &[f32],
+pub fn height_mesh<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    output: &mut HeightMeshBuffer,
+) {
+    height_mesh_with_config(
+        height_map,
+        map_shape,
+        min,
+        max,
+        &HeightMeshConfig::default(),
+        output,
+    )
+}
+
+/// The same as [`height_mesh`], but with the optional outputs described by `config`.
+pub fn height_mesh_with_config<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
     map_shape: &S,
     min: [u32; 2],
     max: [u32; 2],
+    config: &HeightMeshConfig,
     output: &mut HeightMeshBuffer,
 ) {
+    height_mesh_with_row_callback(height_map, map_shape, min, max, config, output, |_, _| {})
+}
+
+/// The same as [`height_mesh_with_config`], but `on_row` is called after each row of vertices is written, with the range of
+/// new vertex indices and the buffer so far.
+///
+/// This lets renderers start uploading or compressing vertex data while the rest of the chunk is still being generated.
+/// Only the per-vertex channels that are computed in the same pass are complete for the new range: positions, normals, and
+/// curvature. Indices and the other channels are written after the last row.
+pub fn height_mesh_with_row_callback<M, S, F>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &HeightMeshConfig,
+    output: &mut HeightMeshBuffer,
+    on_row: F,
+) where
+    M: HeightMapView + ?Sized,
+    S: Shape<u32, 2>,
+    F: FnMut(Range<usize>, &HeightMeshBuffer),
+{
+    let mut stopwatch = Stopwatch::start();
+    let hash = config
+        .content_hash
+        .then(|| content_hash(height_map, map_shape, min, max, config));
+    stopwatch.lap(output, Stage::ContentHash);
+
+    match Composited::new(height_map, map_shape, config) {
+        Some(input) => {
+            let config = detail::remaining_config(config);
+            mesh(&input, map_shape, [0; 2], min, max, &config, output, on_row)
+        }
+        None => mesh(
+            height_map, map_shape, [0; 2], min, max, config, output, on_row,
+        ),
+    }
+
+    output.content_hash = hash.unwrap_or(0);
+    stopwatch.finish(output);
+}
+
+/// The same as [`height_mesh_with_config`] (without the content hash), but `height_map` is a window of a larger map, whose
+/// sample `[0, 0]` is sample `origin` of the larger map. Positions, geodetic spacing, diagonals, detail maps, and world-space
+/// UVs are computed in the coordinates of the larger map, so a large map can be meshed in pieces without buffers the size
+/// of the whole map. The material and texture layer maps must cover the window, not the larger map.
+pub(crate) fn height_mesh_window<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    origin: [u32; 2],
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &HeightMeshConfig,
+    output: &mut HeightMeshBuffer,
+) {
+    let config = HeightMeshConfig {
+        detail: config.detail.map(|detail| DetailMap {
+            offset: [0, 1].map(|i| detail.offset[i].wrapping_add(origin[i])),
+            ..detail
+        }),
+        ..config.clone()
+    };
+    let on_row = |_, _: &_| {};
+    match Composited::new(height_map, map_shape, &config) {
+        Some(input) => {
+            let config = detail::remaining_config(&config);
+            mesh(&input, map_shape, origin, min, max, &config, output, on_row)
+        }
+        None => mesh(
+            height_map, map_shape, origin, min, max, &config, output, on_row,
+        ),
+    }
+    output.content_hash = 0;
+}
+
+/// Meshes `[min, max]` of `height_map`, whose sample `[0, 0]` is at `origin` in the coordinates used for positions and the
+/// position-dependent options.
+#[allow(clippy::too_many_arguments)]
+fn mesh<M, S, F>(
+    height_map: &M,
+    map_shape: &S,
+    origin: [u32; 2],
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &HeightMeshConfig,
+    output: &mut HeightMeshBuffer,
+    mut on_row: F,
+) where
+    M: HeightMapView + ?Sized,
+    S: Shape<u32, 2>,
+    F: FnMut(Range<usize>, &HeightMeshBuffer),
+{
     // SAFETY
     // Check the bounds on the array before we start using get_unchecked.
     assert!((map_shape.linearize(min) as usize) < height_map.len());
     assert!((map_shape.linearize(max) as usize) < height_map.len());
+    if let Some(material_map) = config.material_map {
+        assert!((map_shape.linearize(max) as usize) < material_map.len());
+    }
+    if let Some(layer_map) = config.texture_layer_map {
+        assert!((map_shape.linearize(max) as usize) < layer_map.len());
+    }
 
     output.reset(height_map.len());
+    let mut stopwatch = Stopwatch::start();
 
     let [minx, miny] = min;
     let [maxx, maxy] = max;
@@ -101,46 +523,161 @@ pub fn height_mesh<S: Shape<u32, 2>>(
     let x_stride = map_shape.linearize([1, 0]);
     let y_stride = map_shape.linearize([0, 1]);
 
+    // Most configs only add channels in separate passes, so the vertex pass can skip the per-vertex checks for the options
+    // that change positions and normals.
+    let plain_vertices = config.geodetic.is_none()
+        && config.border_snap.is_none()
+        && matches!(config.normal_mode, NormalMode::CentralDifference)
+        && !config.packed_normals
+        && !config.curvature;
+
     // Note: Although we use (x, y) for the coordinates of the height map, these should be considered (x, z) in world
     // coordinates, because +Y is the UP vector.
     for z in iminy..=imaxy {
+        let row_start = output.positions.len();
+
+        if plain_vertices {
+            plain_vertex_row(
+                height_map,
+                map_shape,
+                origin,
+                [iminx, imaxx],
+                z,
+                config.vertical_scale,
+                output,
+            );
+            on_row(row_start..output.positions.len(), output);
+            continue;
+        }
+
+        // The distance between samples on this row.
+        let [dx, dz] = match &config.geodetic {
+            Some(geodetic) => geodetic.meters_per_sample(origin[1] + z).map(|d| d as f32),
+            None => [1.0; 2],
+        };
+
         for x in iminx..=imaxx {
             let stride = map_shape.linearize([x, z]);
-            let y = height_map[stride as usize];
+            let y = config.vertical_scale * height_map.height(stride as usize);
 
             output.stride_to_index[stride as usize] = output.positions.len() as u32;
-            output.positions.push([x as f32, y, z as f32]);
-
-            // Use central differencing to calculate the surface normal.
-            //
-            // From calculus, we know that gradients are always orthogonal to a level set. The surface approximated by the
-            // height map h(x, z) happens to be the 0 level set of the function:
-            //
-            // f(x, y, z) = y - h(x, z)
-            //
-            // And the gradient is:
-            //
-            // grad f = [-dh/dx, 1, -dh/dz]
-            let l_stride = stride - x_stride;
-            let r_stride = stride + x_stride;
-            let b_stride = stride - y_stride;
-            let t_stride = stride + y_stride;
-            let l_y = unsafe { height_map.get_unchecked(l_stride as usize) };
-            let r_y = unsafe { height_map.get_unchecked(r_stride as usize) };
-            let b_y = unsafe { height_map.get_unchecked(b_stride as usize) };
-            let t_y = unsafe { height_map.get_unchecked(t_stride as usize) };
-            let dy_dx = (r_y - l_y) / 2.0;
-            let dy_dz = (t_y - b_y) / 2.0;
-            // Not normalized, because that's done more efficiently on the GPU.
-            output.normals.push([-dy_dx, 1.0, -dy_dz]);
+            let map_p = [origin[0] + x, origin[1] + z];
+            let [px, pz] = match &config.geodetic {
+                Some(geodetic) => geodetic.position(map_p).map(|p| p as f32),
+                None => map_p.map(|c| c as f32),
+            };
+            let mut position = [px, y, pz];
+            if let Some(lattice) = config.border_snap {
+                if x == iminx || x == imaxx || z == iminy || z == imaxy {
+                    position = snap_to_lattice(position, lattice);
+                }
+            }
+            output.positions.push(position);
+
+            let normal = vertex_normal(
+                height_map,
+                map_shape,
+                [[iminx, iminy], [imaxx, imaxy]],
+                config,
+                [x, z],
+                [dx, dz],
+            );
+            push_normal(output, config, normal);
+
+            if config.curvature {
+                output
+                    .curvature
+                    .push(vertex_curvature(height_map, map_shape, config, [x, z]));
+            }
         }
+
+        on_row(row_start..output.positions.len(), output);
+    }
+
+    stopwatch.lap(output, Stage::Vertices);
+
+    if let Some(ao_config) = &config.ambient_occlusion {
+        ao::ambient_occlusion(
+            height_map,
+            map_shape,
+            min,
+            max,
+            [iminx, iminy],
+            [imaxx, imaxy],
+            config.vertical_scale,
+            ao_config,
+            &mut output.ambient_occlusion,
+        );
     }
 
+    stopwatch.lap(output, Stage::AmbientOcclusion);
+
+    let grid_size = [
+        (imaxx + 1).saturating_sub(iminx),
+        (imaxy + 1).saturating_sub(iminy),
+    ];
+
+    if let Some(mode) = &config.uvs {
+        uvs::uvs(
+            mode,
+            [origin[0] + iminx, origin[1] + iminy],
+            grid_size,
+            &mut output.uvs,
+        );
+    }
+
+    if let Some(lightmap) = &config.lightmap_uvs {
+        uvs::lightmap_uvs(lightmap, grid_size, &mut output.lightmap_uvs);
+    }
+
+    if let Some(layer_map) = config.texture_layer_map {
+        texture_layers::texture_layers(
+            layer_map,
+            map_shape,
+            [iminx, iminy],
+            [imaxx, imaxy],
+            output,
+        );
+    }
+
+    stopwatch.lap(output, Stage::Uvs);
+
+    if let Some(step) = config.shadow_caster_step {
+        shadow_caster_mesh(map_shape, [iminx, iminy], [imaxx, imaxy], step, output);
+    }
+
+    output.lod_indices.truncate(config.lod_steps.len());
+    output
+        .lod_indices
+        .resize_with(config.lod_steps.len(), Vec::new);
+    for (&step, indices) in config.lod_steps.iter().zip(output.lod_indices.iter_mut()) {
+        lod_indices(
+            map_shape,
+            [iminx, iminy],
+            [imaxx, imaxy],
+            step,
+            &output.stride_to_index,
+            indices,
+        );
+    }
+
+    stopwatch.lap(output, Stage::CoarseIndices);
+
     // Only add a quad when p is the bottom-left corner of a quad that fits in the interior.
     let imaxx = imaxx - 1;
     let imaxy = imaxy - 1;
 
+    let plain_indices = config.diagonals == DiagonalPattern::Uniform
+        && config.material_map.is_none()
+        && !config.triangle_cells
+        && !config.line_indices;
+
     for z in iminy..=imaxy {
+        if plain_indices {
+            plain_index_row(map_shape, [iminx, imaxx], z, output);
+            continue;
+        }
+
         for x in iminx..=imaxx {
             let bl_stride = map_shape.linearize([x, z]);
             let br_stride = bl_stride + x_stride;
@@ -152,9 +689,336 @@ pub fn height_mesh<S: Shape<u32, 2>>(
             let tl_index = output.stride_to_index[tl_stride as usize];
             let tr_index = output.stride_to_index[tr_stride as usize];
 
-            output
-                .indices
-                .extend_from_slice(&[bl_index, tl_index, tr_index, bl_index, tr_index, br_index]);
+            let flipped = config.diagonals.is_flipped([origin[0] + x, origin[1] + z]);
+            output.indices.extend_from_slice(&diagonals::quad_indices(
+                [bl_index, br_index, tl_index, tr_index],
+                flipped,
+            ));
+
+            if let Some(material_map) = config.material_map {
+                let material = material_map[bl_stride as usize];
+                output.triangle_materials.extend_from_slice(&[material; 2]);
+            }
+            if config.triangle_cells {
+                output.triangle_cells.extend_from_slice(&[bl_stride; 2]);
+            }
+
+            if config.line_indices {
+                // Each quad owns its left, bottom, and diagonal edges. The top and right edges are only owned by the quads
+                // in the last row and column, so that no edge is emitted twice.
+                let diagonal = if flipped {
+                    [tl_index, br_index]
+                } else {
+                    [bl_index, tr_index]
+                };
+                output.line_indices.extend_from_slice(&[
+                    bl_index,
+                    tl_index,
+                    bl_index,
+                    br_index,
+                    diagonal[0],
+                    diagonal[1],
+                ]);
+                if x == imaxx {
+                    output.line_indices.extend_from_slice(&[br_index, tr_index]);
+                }
+                if z == imaxy {
+                    output.line_indices.extend_from_slice(&[tl_index, tr_index]);
+                }
+            }
+        }
+    }
+
+    stopwatch.lap(output, Stage::Indices);
+
+    if let Some(max_edge_length) = config.max_edge_length {
+        tessellate::subdivide_long_edges(
+            grid_size,
+            max_edge_length,
+            |[cx, cz]| {
+                config
+                    .diagonals
+                    .is_flipped([origin[0] + iminx + cx, origin[1] + iminy + cz])
+            },
+            output,
+        );
+    }
+
+    if let Some(max_slope) = config.walkable_slope {
+        partition_by_slope(max_slope, output);
+    }
+
+    stopwatch.lap(output, Stage::Post);
+}
+
+/// The surface normal at the interior point `p`, where `spacing` is the distance between samples around it.
+///
+/// The caller must have checked that the interior is in bounds.
+pub(crate) fn vertex_normal<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    [imin, imax]: [[u32; 2]; 2],
+    config: &HeightMeshConfig,
+    p: [u32; 2],
+    [dx, dz]: [f32; 2],
+) -> [f32; 3] {
+    // Use the gradient of the height map (by central differencing, unless configured otherwise) to calculate the surface
+    // normal.
+    //
+    // From calculus, we know that gradients are always orthogonal to a level set. The surface approximated by the height
+    // map h(x, z) happens to be the 0 level set of the function:
+    //
+    // f(x, y, z) = y - h(x, z)
+    //
+    // And the gradient is:
+    //
+    // grad f = [-dh/dx, 1, -dh/dz]
+    let [dy_dx, dy_dz] = match config.normal_mode {
+        NormalMode::CentralDifference => {
+            let stride = map_shape.linearize(p);
+            let x_stride = map_shape.linearize([1, 0]);
+            let y_stride = map_shape.linearize([0, 1]);
+            let l_stride = stride - x_stride;
+            let r_stride = stride + x_stride;
+            let b_stride = stride - y_stride;
+            let t_stride = stride + y_stride;
+            let l_y = unsafe { height_map.height_unchecked(l_stride as usize) };
+            let r_y = unsafe { height_map.height_unchecked(r_stride as usize) };
+            let b_y = unsafe { height_map.height_unchecked(b_stride as usize) };
+            let t_y = unsafe { height_map.height_unchecked(t_stride as usize) };
+            [(r_y - l_y) / 2.0, (t_y - b_y) / 2.0]
+        }
+        mode => normals::gradient(height_map, map_shape, imin, imax, &mode, p),
+    };
+    let dy_dx = config.vertical_scale * dy_dx / dx;
+    let dy_dz = config.vertical_scale * dy_dz / dz;
+    // Not normalized, because that's done more efficiently on the GPU.
+    [-dy_dx, 1.0, -dy_dz]
+}
+
+/// Writes the normal of the next vertex to the channel chosen by [`HeightMeshConfig::packed_normals`].
+#[inline]
+pub(crate) fn push_normal(
+    output: &mut HeightMeshBuffer,
+    config: &HeightMeshConfig,
+    normal: [f32; 3],
+) {
+    if config.packed_normals {
+        output.packed_normals.push(normals::pack(normal));
+    } else {
+        output.normals.push(normal);
+    }
+}
+
+/// The vertex pass over row `z` for configs without any per-vertex options, producing exactly the same positions and
+/// normals as the general pass.
+///
+/// The caller must have checked that the interior is in bounds.
+#[inline]
+fn plain_vertex_row<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    origin: [u32; 2],
+    [iminx, imaxx]: [u32; 2],
+    z: u32,
+    vertical_scale: f32,
+    output: &mut HeightMeshBuffer,
+) {
+    let x_stride = map_shape.linearize([1, 0]) as usize;
+    let y_stride = map_shape.linearize([0, 1]) as usize;
+    for x in iminx..=imaxx {
+        let stride = map_shape.linearize([x, z]) as usize;
+        output.stride_to_index[stride] = output.positions.len() as u32;
+
+        // SAFETY: the caller checked that the interior and its neighbors are in bounds.
+        let [y, l_y, r_y, b_y, t_y] = unsafe {
+            [
+                height_map.height_unchecked(stride),
+                height_map.height_unchecked(stride - x_stride),
+                height_map.height_unchecked(stride + x_stride),
+                height_map.height_unchecked(stride - y_stride),
+                height_map.height_unchecked(stride + y_stride),
+            ]
+        };
+        output.positions.push([
+            (origin[0] + x) as f32,
+            vertical_scale * y,
+            (origin[1] + z) as f32,
+        ]);
+        let dy_dx = vertical_scale * ((r_y - l_y) / 2.0);
+        let dy_dz = vertical_scale * ((t_y - b_y) / 2.0);
+        output.normals.push([-dy_dx, 1.0, -dy_dz]);
+    }
+}
+
+/// The index pass over row `z` for configs that use the uniform diagonal and no per-triangle channels.
+#[inline]
+fn plain_index_row<S: Shape<u32, 2>>(
+    map_shape: &S,
+    [iminx, imaxx]: [u32; 2],
+    z: u32,
+    output: &mut HeightMeshBuffer,
+) {
+    let x_stride = map_shape.linearize([1, 0]);
+    let y_stride = map_shape.linearize([0, 1]);
+    for x in iminx..=imaxx {
+        let bl_stride = map_shape.linearize([x, z]);
+        let bl = output.stride_to_index[bl_stride as usize];
+        let br = output.stride_to_index[(bl_stride + x_stride) as usize];
+        let tl = output.stride_to_index[(bl_stride + y_stride) as usize];
+        let tr = output.stride_to_index[(bl_stride + x_stride + y_stride) as usize];
+        output.indices.extend_from_slice(&[bl, tl, tr, bl, tr, br]);
+    }
+}
+
+pub(crate) fn partition_by_slope(max_slope: f32, output: &mut HeightMeshBuffer) {
+    // A triangle's slope is the angle between its normal and +Y, so compare the cosines instead.
+    let min_cos = max_slope.cos();
+    for tri in output.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| output.positions[tri[i] as usize]);
+        let n = normals::cross(normals::sub(b, a), normals::sub(c, a));
+        let length = normals::dot(n, n).sqrt();
+        let cos = if length > 0.0 { n[1] / length } else { 1.0 };
+        if cos >= min_cos {
+            output.walkable_indices.extend_from_slice(tri);
+        } else {
+            output.steep_indices.extend_from_slice(tri);
+        }
+    }
+}
+
+/// Rounds each coordinate of `p` to the nearest multiple of `lattice`.
+pub(crate) fn snap_to_lattice(p: [f32; 3], lattice: f32) -> [f32; 3] {
+    p.map(|c| (c / lattice).round() * lattice)
+}
+
+/// The Laplacian at the interior point `p`, from the second differences along each axis.
+fn vertex_curvature<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    config: &HeightMeshConfig,
+    [x, z]: [u32; 2],
+) -> f32 {
+    let h = |p: [u32; 2]| height_map.height(map_shape.linearize(p) as usize);
+    let c = h([x, z]);
+    let l = h([x - 1, z]);
+    let r = h([x + 1, z]);
+    let b = h([x, z - 1]);
+    let t = h([x, z + 1]);
+    config.vertical_scale * (l + r + b + t - 4.0 * c)
+}
+
+pub(crate) fn shadow_caster_mesh<S: Shape<u32, 2>>(
+    map_shape: &S,
+    imin: [u32; 2],
+    imax: [u32; 2],
+    step: u32,
+    output: &mut HeightMeshBuffer,
+) {
+    assert!(step > 0);
+    if imax[0] < imin[0] || imax[1] < imin[1] {
+        // The interior is empty, so there is nothing to cast a shadow.
+        return;
+    }
+
+    let xs = occluder::coarse_lines(imin[0], imax[0], step);
+    let zs = occluder::coarse_lines(imin[1], imax[1], step);
+    let shadow = &mut output.shadow_caster;
+    for &z in zs.iter() {
+        for &x in xs.iter() {
+            let index = output.stride_to_index[map_shape.linearize([x, z]) as usize];
+            shadow.positions.push(output.positions[index as usize]);
         }
     }
+
+    push_grid_indices(xs.len() as u32, zs.len() as u32, &mut shadow.indices);
+}
+
+fn lod_indices<S: Shape<u32, 2>>(
+    map_shape: &S,
+    imin: [u32; 2],
+    imax: [u32; 2],
+    step: u32,
+    stride_to_index: &[u32],
+    indices: &mut Vec<u32>,
+) {
+    assert!(step > 0);
+
+    let xs = occluder::coarse_lines(imin[0], imax[0], step);
+    let zs = occluder::coarse_lines(imin[1], imax[1], step);
+    let index = |x: u32, z: u32| stride_to_index[map_shape.linearize([x, z]) as usize];
+    for cz in zs.windows(2) {
+        for cx in xs.windows(2) {
+            let bl = index(cx[0], cz[0]);
+            let br = index(cx[1], cz[0]);
+            let tl = index(cx[0], cz[1]);
+            let tr = index(cx[1], cz[1]);
+            indices.extend_from_slice(&[bl, tl, tr, bl, tr, br]);
+        }
+    }
+}
+
+/// Triangulates a row-major grid of `width * height` vertices with the same winding as [`height_mesh`].
+pub(crate) fn push_grid_indices(width: u32, height: u32, indices: &mut Vec<u32>) {
+    for cz in 0..height.saturating_sub(1) {
+        for cx in 0..width.saturating_sub(1) {
+            let bl = cz * width + cx;
+            let br = bl + 1;
+            let tl = bl + width;
+            let tr = tl + 1;
+            indices.extend_from_slice(&[bl, tl, tr, bl, tr, br]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ndshape::{ConstShape, ConstShape2u32};
+
+    #[test]
+    fn empty_interior_has_empty_coarse_meshes() {
+        type MapShape = ConstShape2u32<6, 6>;
+        let heights = [1.0; MapShape::SIZE as usize];
+        let config = HeightMeshConfig {
+            shadow_caster_step: Some(2),
+            lod_steps: &[2],
+            ..Default::default()
+        };
+        // A region one sample wide has no interior along X.
+        for max in [[3, 4], [4, 3], [2, 2]] {
+            let mut buffer = HeightMeshBuffer::default();
+            height_mesh_with_config(&heights, &MapShape {}, [2; 2], max, &config, &mut buffer);
+            assert!(buffer.positions.is_empty() && buffer.indices.is_empty());
+            assert!(buffer.shadow_caster.positions.is_empty());
+            assert!(buffer.shadow_caster.indices.is_empty());
+            assert!(buffer.lod_indices[0].is_empty());
+
+            decimated_height_mesh(&heights, &MapShape {}, [2; 2], max, 2, &config, &mut buffer);
+            assert!(buffer.positions.is_empty() && buffer.indices.is_empty());
+
+            let mut occluder = CoarseMeshBuffer::default();
+            occluder_mesh(&heights, &MapShape {}, [2; 2], max, 2, &mut occluder);
+            assert!(occluder.positions.is_empty() && occluder.indices.is_empty());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "detail map must not be empty")]
+    fn empty_detail_map_is_rejected() {
+        type MapShape = ConstShape2u32<6, 6>;
+        let heights = [1.0; MapShape::SIZE as usize];
+        let config = HeightMeshConfig {
+            detail: Some(DetailMap {
+                heights: &[],
+                size: [0, 0],
+                weight: 1.0,
+                offset: [0, 0],
+            }),
+            ..Default::default()
+        };
+        let mut buffer = HeightMeshBuffer::default();
+        height_mesh_with_config(&heights, &MapShape {}, [0; 2], [5; 2], &config, &mut buffer);
+    }
 }
@@ -0,0 +1,91 @@
+use crate::HeightMeshBuffer;
+
+use std::ops::Range;
+
+/// A contiguous range of [`HeightMeshBuffer::indices`] whose triangles all share one material.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaterialRange {
+    /// The material ID from the [`HeightMeshConfig::material_map`](crate::HeightMeshConfig::material_map).
+    pub material: u8,
+    /// The range of `indices` (not triangles) that use this material. Suitable for a single submesh draw call.
+    pub indices: Range<usize>,
+}
+
+impl HeightMeshBuffer {
+    /// Reorders the triangles so that each material's triangles are contiguous, then returns the index range of each
+    /// material that occurs, in ascending order of material ID.
+    ///
+    /// Requires [`HeightMeshBuffer::triangle_materials`] to be populated, i.e. the mesh was generated with a
+    /// [`HeightMeshConfig::material_map`](crate::HeightMeshConfig::material_map). The relative order of triangles within a
+    /// material is preserved, and [`HeightMeshBuffer::triangle_cells`] is reordered along with the triangles.
+    pub fn split_by_material(&mut self) -> Vec<MaterialRange> {
+        let num_triangles = self.indices.len() / 3;
+        assert_eq!(
+            self.triangle_materials.len(),
+            num_triangles,
+            "Mesh was generated without a material map"
+        );
+
+        // Counting sort, since there are at most 256 materials.
+        let mut counts = [0usize; 256];
+        for &material in self.triangle_materials.iter() {
+            counts[material as usize] += 1;
+        }
+        let mut offsets = [0usize; 256];
+        let mut sum = 0;
+        for (offset, &count) in offsets.iter_mut().zip(counts.iter()) {
+            *offset = sum;
+            sum += count;
+        }
+
+        let mut ranges = Vec::new();
+        for (material, (&offset, &count)) in offsets.iter().zip(counts.iter()).enumerate() {
+            if count > 0 {
+                ranges.push(MaterialRange {
+                    material: material as u8,
+                    indices: 3 * offset..3 * (offset + count),
+                });
+            }
+        }
+
+        let mut sorted_indices = vec![0; self.indices.len()];
+        let mut sorted_materials = vec![0; num_triangles];
+        let has_cells = self.triangle_cells.len() == num_triangles;
+        let mut sorted_cells = vec![0; if has_cells { num_triangles } else { 0 }];
+        for (triangle, &material) in self.triangle_materials.iter().enumerate() {
+            let dst = offsets[material as usize];
+            offsets[material as usize] += 1;
+            sorted_indices[3 * dst..3 * dst + 3]
+                .copy_from_slice(&self.indices[3 * triangle..3 * triangle + 3]);
+            sorted_materials[dst] = material;
+            if has_cells {
+                sorted_cells[dst] = self.triangle_cells[triangle];
+            }
+        }
+        self.indices = sorted_indices;
+        self.triangle_materials = sorted_materials;
+        if has_cells {
+            self.triangle_cells = sorted_cells;
+        }
+
+        ranges
+    }
+
+    /// Looks up the per-cell value of each triangle in `cell_data`, a map with the same shape as the height map that the
+    /// mesh was generated from, so gameplay data like province IDs or navigation flags stays aligned with the triangles.
+    ///
+    /// Requires [`HeightMeshBuffer::triangle_cells`] to be populated, i.e. the mesh was generated with
+    /// [`HeightMeshConfig::triangle_cells`](crate::HeightMeshConfig::triangle_cells). As with the material map, a cell is
+    /// identified by its bottom-left corner sample.
+    pub fn triangle_data<T: Copy>(&self, cell_data: &[T]) -> Vec<T> {
+        assert_eq!(
+            self.triangle_cells.len() * 3,
+            self.indices.len(),
+            "Mesh was generated without triangle cells"
+        );
+        self.triangle_cells
+            .iter()
+            .map(|&stride| cell_data[stride as usize])
+            .collect()
+    }
+}
@@ -0,0 +1,189 @@
+//! Helpers for meshing Web Mercator ("slippy map") elevation tiles, for 3D terrain overlays on web maps.
+//!
+//! A tile is decoded into heights with [`decode_terrain_rgb`] (or supplied directly as DEM samples), then meshed with
+//! [`tile_mesh`], which places the vertices in local meters with the true ground scale of the tile.
+//!
+//! ```
+//! use height_mesh::mercator::{decode_terrain_rgb, tile_mesh, TerrainEncoding, TileId};
+//! use height_mesh::{HeightMeshBuffer, HeightMeshConfig};
+//!
+//! // A flat 4x4 RGB tile at sea level.
+//! let pixels = [0x01, 0x86, 0xa0].repeat(16);
+//! let heights = decode_terrain_rgb(&pixels, 3, TerrainEncoding::Mapbox);
+//! assert_eq!(heights[0], 0.0);
+//!
+//! let tile = TileId { zoom: 12, x: 2200, y: 1343 };
+//! let mut buffer = HeightMeshBuffer::default();
+//! tile_mesh(&heights, &tile, 4, &HeightMeshConfig::default(), &mut buffer);
+//! assert_eq!(buffer.positions.len(), 4);
+//! ```
+
+use crate::normals;
+use crate::{
+    height_mesh_with_config, partition_by_slope, HeightMapView, HeightMeshBuffer, HeightMeshConfig,
+};
+
+use ndshape::Shape2u32;
+use std::f64::consts::PI;
+
+/// The equatorial radius used by Web Mercator (EPSG:3857), in meters.
+pub const EARTH_RADIUS: f64 = 6_378_137.0;
+
+/// How elevation is packed into the RGB channels of a tile image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TerrainEncoding {
+    /// Mapbox Terrain-RGB: `-10000 + (R * 65536 + G * 256 + B) * 0.1`.
+    Mapbox,
+    /// Terrarium, as served by AWS Terrain Tiles: `R * 256 + G + B / 256 - 32768`.
+    Terrarium,
+}
+
+/// Decodes the heights (in meters) of an 8-bit image with `channels` interleaved channels per pixel, of which the first
+/// three are RGB.
+pub fn decode_terrain_rgb(pixels: &[u8], channels: usize, encoding: TerrainEncoding) -> Vec<f32> {
+    assert!(channels >= 3);
+    pixels
+        .chunks_exact(channels)
+        .map(|p| {
+            let [r, g, b] = [p[0] as f64, p[1] as f64, p[2] as f64];
+            let height = match encoding {
+                TerrainEncoding::Mapbox => -10_000.0 + (r * 65_536.0 + g * 256.0 + b) * 0.1,
+                TerrainEncoding::Terrarium => r * 256.0 + g + b / 256.0 - 32_768.0,
+            };
+            height as f32
+        })
+        .collect()
+}
+
+/// The `zoom/x/y` address of a tile. `y` increases southward, as in XYZ tile URLs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TileId {
+    pub zoom: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileId {
+    /// The latitude in degrees at `v`, the vertical position within the tile where 0 is the north edge and 1 is the south
+    /// edge.
+    pub fn latitude(&self, v: f64) -> f64 {
+        let n = PI * (1.0 - 2.0 * (self.y as f64 + v) / self.num_tiles());
+        n.sinh().atan().to_degrees()
+    }
+
+    /// The longitude in degrees at `u`, the horizontal position within the tile where 0 is the west edge and 1 is the east
+    /// edge.
+    pub fn longitude(&self, u: f64) -> f64 {
+        (self.x as f64 + u) / self.num_tiles() * 360.0 - 180.0
+    }
+
+    /// The `[west, south, east, north]` bounds of the tile in degrees.
+    pub fn bounds(&self) -> [f64; 4] {
+        [
+            self.longitude(0.0),
+            self.latitude(1.0),
+            self.longitude(1.0),
+            self.latitude(0.0),
+        ]
+    }
+
+    /// The ground distance in meters covered by one pixel at `v` (see [`TileId::latitude`]), for tiles of `tile_size`
+    /// pixels on a side. Mercator is conformal, so this is the same along both axes.
+    pub fn meters_per_pixel(&self, v: f64, tile_size: u32) -> f64 {
+        let circumference = 2.0 * PI * EARTH_RADIUS;
+        circumference * self.latitude(v).to_radians().cos() / (self.num_tiles() * tile_size as f64)
+    }
+
+    fn num_tiles(&self) -> f64 {
+        (1u64 << self.zoom) as f64
+    }
+}
+
+/// Meshes a square tile of `tile_size` by `tile_size` heights in meters, with the rows of `height_map` running from north
+/// to south as in the tile image.
+///
+/// Vertex positions are in meters relative to the center of the north-west pixel, with +X pointing east and +Z pointing
+/// south. Each row is scaled by the ground resolution at its latitude, so the mesh has the correct proportions even for
+/// low zoom levels where the scale varies across the tile, and the normals are consistent with the scaled positions.
+///
+/// Like [`height_mesh`](crate::height_mesh), the outermost pixels are only used for normals, so neighboring tiles should be
+/// padded (or backfilled from their neighbors) to mesh seamlessly. [`HeightMeshConfig::geodetic`] is ignored, and
+/// [`HeightMeshConfig::walkable_slope`] is applied to the slopes in meters.
+///
+/// # Panics
+///
+/// Panics if [`HeightMeshConfig::max_edge_length`] or [`HeightMeshConfig::border_snap`] is set, since they move vertices
+/// off the sample grid that the conversion to meters relies on.
+pub fn tile_mesh<M: HeightMapView + ?Sized>(
+    height_map: &M,
+    tile: &TileId,
+    tile_size: u32,
+    config: &HeightMeshConfig,
+    output: &mut HeightMeshBuffer,
+) {
+    assert_eq!(height_map.len(), (tile_size * tile_size) as usize);
+    assert!(
+        config.max_edge_length.is_none(),
+        "tile_mesh doesn't support max_edge_length"
+    );
+    assert!(
+        config.border_snap.is_none(),
+        "tile_mesh doesn't support border_snap"
+    );
+
+    // The slopes are only meaningful once the positions are in meters.
+    let walkable_slope = config.walkable_slope;
+    let config = HeightMeshConfig {
+        geodetic: None,
+        walkable_slope: None,
+        ..config.clone()
+    };
+    let shape = Shape2u32::new([tile_size, tile_size]);
+    height_mesh_with_config(
+        height_map,
+        &shape,
+        [0; 2],
+        [tile_size - 1; 2],
+        &config,
+        output,
+    );
+
+    // The ground resolution at each row (at the pixel center), and the distance of each row from row 0.
+    let size = tile_size as f64;
+    let row_scale: Vec<f64> = (0..tile_size)
+        .map(|row| tile.meters_per_pixel((row as f64 + 0.5) / size, tile_size))
+        .collect();
+    let mut row_z = Vec::with_capacity(tile_size as usize);
+    let mut z = 0.0;
+    for row in 0..tile_size {
+        row_z.push(z);
+        // The distance to the next row, using the resolution halfway between them.
+        z += tile.meters_per_pixel((row as f64 + 1.0) / size, tile_size);
+    }
+
+    // The mesher works in sample units, so each vertex's row and column can be read back from its position.
+    let to_meters = |p: &mut [f32; 3]| -> f32 {
+        let row = p[2] as usize;
+        let scale = row_scale[row];
+        p[0] = (p[0] as f64 * scale) as f32;
+        p[2] = row_z[row] as f32;
+        scale as f32
+    };
+    for (i, p) in output.positions.iter_mut().enumerate() {
+        let scale = to_meters(p);
+        if let Some(n) = output.normals.get_mut(i) {
+            n[0] /= scale;
+            n[2] /= scale;
+        } else if let Some(n) = output.packed_normals.get_mut(i) {
+            let [x, y, z] = normals::unpack(*n);
+            *n = normals::pack([x / scale, y, z / scale]);
+        }
+    }
+    for p in output.shadow_caster.positions.iter_mut() {
+        to_meters(p);
+    }
+
+    if let Some(max_slope) = walkable_slope {
+        partition_by_slope(max_slope, output);
+    }
+}
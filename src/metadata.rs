@@ -0,0 +1,85 @@
+/// Describes the real-world scale and placement of a mesh. Attach it to [`HeightMeshBuffer::metadata`](crate::HeightMeshBuffer::metadata)
+/// so that exporters can pass it on to downstream tools.
+///
+/// The mesher itself always works in sample units; this is purely descriptive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpatialMetadata {
+    /// The real-world distance between adjacent samples along X and Z, in `horizontal_units`.
+    pub horizontal_spacing: [f64; 2],
+    /// The unit of `horizontal_spacing` and `origin`, e.g. `"m"`.
+    pub horizontal_units: String,
+    /// The unit of the height values, e.g. `"m"` or `"ft"`.
+    pub vertical_units: String,
+    /// A coordinate reference system tag, e.g. `"EPSG:32633"`.
+    pub crs: Option<String>,
+    /// The real-world `[x, y, z]` coordinates of sample `[0, 0]` at height 0.
+    pub origin: [f64; 3],
+}
+
+impl Default for SpatialMetadata {
+    fn default() -> Self {
+        Self {
+            horizontal_spacing: [1.0; 2],
+            horizontal_units: "m".into(),
+            vertical_units: "m".into(),
+            crs: None,
+            origin: [0.0; 3],
+        }
+    }
+}
+
+impl SpatialMetadata {
+    /// Formats the metadata as a JSON object, suitable for the `extras` property of a glTF node or mesh.
+    pub fn to_gltf_extras(&self) -> String {
+        let crs = match &self.crs {
+            Some(crs) => json_string(crs),
+            None => "null".into(),
+        };
+        format!(
+            "{{\"horizontal_spacing\":[{},{}],\"horizontal_units\":{},\"vertical_units\":{},\"crs\":{},\"origin\":[{},{},{}]}}",
+            self.horizontal_spacing[0],
+            self.horizontal_spacing[1],
+            json_string(&self.horizontal_units),
+            json_string(&self.vertical_units),
+            crs,
+            self.origin[0],
+            self.origin[1],
+            self.origin[2],
+        )
+    }
+
+    /// The metadata as `key value` lines, used for comments in text formats like OBJ and PLY.
+    pub(crate) fn comment_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!(
+                "horizontal_spacing {} {}",
+                self.horizontal_spacing[0], self.horizontal_spacing[1]
+            ),
+            format!("horizontal_units {}", self.horizontal_units),
+            format!("vertical_units {}", self.vertical_units),
+        ];
+        if let Some(crs) = &self.crs {
+            lines.push(format!("crs {}", crs));
+        }
+        lines.push(format!(
+            "origin {} {} {}",
+            self.origin[0], self.origin[1], self.origin[2]
+        ));
+        lines
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,47 @@
+use crate::HeightMeshBuffer;
+
+/// The coordinate that [`HeightMeshBuffer::mirror`] negates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MirrorAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl HeightMeshBuffer {
+    /// Reflects the mesh across the plane through the origin that is perpendicular to `axis`, for symmetric maps or for
+    /// engines whose coordinate system has the opposite handedness. Translate the mesh first to mirror it across another
+    /// plane.
+    ///
+    /// A reflection turns every triangle inside out, so the winding of all triangle index buffers (including the LODs,
+    /// slope partitions, and shadow caster) is reversed to keep front faces facing the same way as the reflected normals.
+    pub fn mirror(&mut self, axis: MirrorAxis) {
+        let axis = axis as usize;
+        for p in self.positions.iter_mut() {
+            p[axis] = -p[axis];
+        }
+        for n in self.normals.iter_mut() {
+            n[axis] = -n[axis];
+        }
+        for n in self.packed_normals.iter_mut() {
+            n[axis] = -n[axis];
+        }
+        for p in self.shadow_caster.positions.iter_mut() {
+            p[axis] = -p[axis];
+        }
+
+        for indices in [
+            &mut self.indices,
+            &mut self.walkable_indices,
+            &mut self.steep_indices,
+            &mut self.shadow_caster.indices,
+        ]
+        .into_iter()
+        .chain(self.lod_indices.iter_mut())
+        {
+            for tri in indices.chunks_exact_mut(3) {
+                tri.swap(1, 2);
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{HeightMapView, HeightMeshBuffer};
+
+use ndshape::Shape;
+
+/// How the mesher estimates the surface normal at each vertex. See
+/// [`HeightMeshConfig::normal_mode`](crate::HeightMeshConfig::normal_mode).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum NormalMode {
+    /// Central differencing of the 4 direct neighbors. Fast and exact for smooth input.
+    #[default]
+    CentralDifference,
+    /// A Gaussian-weighted average of the central-difference gradients within `radius` samples, with a standard deviation of
+    /// half the radius.
+    ///
+    /// Low-precision (e.g. 8-bit) height maps are full of small terraces, which central differencing turns into visibly
+    /// faceted shading. Smoothing the gradient hides them without changing the positions. Gradients are only evaluated at
+    /// the vertices of the mesh, so the window is clamped to them, and vertices within `radius` of the edge of the mesh are
+    /// smoothed one-sidedly. Adjacent chunks therefore disagree on the normals near their shared border; use
+    /// [`HeightMeshBuffer::blend_border_normals`] to make them match.
+    Smoothed { radius: u32 },
+    /// The Sobel operator over the full 3x3 neighborhood, which weights the direct neighbors twice as much as the diagonal
+    /// ones.
+    ///
+    /// Central differencing ignores the diagonal neighbors, so ridges and valleys that run diagonally to the grid get
+    /// axis-aligned shading artifacts. Sobel is nearly isotropic at little extra cost, and it also smooths slightly.
+    Sobel,
+}
+
+impl NormalMode {
+    /// How far away a height sample can be (along each axis) and still affect a vertex normal.
+    pub(crate) fn footprint(&self) -> u32 {
+        match *self {
+            NormalMode::CentralDifference | NormalMode::Sobel => 1,
+            NormalMode::Smoothed { radius } => radius + 1,
+        }
+    }
+}
+
+/// How triangle normals are accumulated by [`HeightMeshBuffer::recompute_normals`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalWeighting {
+    /// Each triangle contributes in proportion to its area. Cheap, and favors large triangles.
+    Area,
+    /// Each triangle contributes in proportion to its interior angle at the vertex. Insensitive to how the surface around a
+    /// vertex happens to be tessellated.
+    Angle,
+}
+
+impl HeightMeshBuffer {
+    /// The normal of vertex `i`, unpacked from [`HeightMeshBuffer::packed_normals`] if the mesh has no
+    /// [`HeightMeshBuffer::normals`], or `None` if the mesh has neither.
+    #[inline]
+    pub fn normal(&self, i: usize) -> Option<[f32; 3]> {
+        match self.normals.get(i) {
+            Some(&n) => Some(n),
+            None => self.packed_normals.get(i).map(|&n| unpack(n)),
+        }
+    }
+
+    /// Returns true if every vertex has a normal in either [`HeightMeshBuffer::normals`] or
+    /// [`HeightMeshBuffer::packed_normals`].
+    pub fn has_normals(&self) -> bool {
+        let n = self.positions.len();
+        self.normals.len() == n || self.packed_normals.len() == n
+    }
+
+    /// Replaces the normals with normals accumulated from the triangles that share each vertex.
+    ///
+    /// The gradient normals from the mesher describe the height map, not the triangles, so they stop matching the geometry
+    /// after welding, simplification, or editing positions. Vertices that aren't referenced by any triangle get a zero
+    /// normal. Like the mesher's normals, the results are **not** normalized. They are always written to `normals`, and
+    /// any [`HeightMeshBuffer::packed_normals`] are cleared.
+    pub fn recompute_normals(&mut self, weighting: NormalWeighting) {
+        self.packed_normals.clear();
+        self.normals.clear();
+        self.normals.resize(self.positions.len(), [0.0; 3]);
+
+        for tri in self.indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0] as usize, tri[1] as usize, tri[2] as usize];
+            let [pa, pb, pc] = [self.positions[a], self.positions[b], self.positions[c]];
+            // The magnitude of the cross product is twice the triangle's area.
+            let face_normal = cross(sub(pb, pa), sub(pc, pa));
+
+            match weighting {
+                NormalWeighting::Area => {
+                    for v in [a, b, c] {
+                        self.normals[v] = add(self.normals[v], face_normal);
+                    }
+                }
+                NormalWeighting::Angle => {
+                    let length = dot(face_normal, face_normal).sqrt();
+                    if length == 0.0 {
+                        continue;
+                    }
+                    let unit_normal = scale(face_normal, 1.0 / length);
+                    for (v, p, p1, p2) in [(a, pa, pb, pc), (b, pb, pc, pa), (c, pc, pa, pb)] {
+                        let weight = angle(sub(p1, p), sub(p2, p));
+                        self.normals[v] = add(self.normals[v], scale(unit_normal, weight));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn angle(u: [f32; 3], v: [f32; 3]) -> f32 {
+    let denom = (dot(u, u) * dot(v, v)).sqrt();
+    if denom == 0.0 {
+        return 0.0;
+    }
+    (dot(u, v) / denom).clamp(-1.0, 1.0).acos()
+}
+
+pub(crate) fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+pub(crate) fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub(crate) fn scale(a: [f32; 3], s: f32) -> [f32; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+pub(crate) fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub(crate) fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// Normalizes `n` and packs it into signed normalized bytes, with a zero pad.
+pub(crate) fn pack(n: [f32; 3]) -> [i8; 4] {
+    let [x, y, z] = normalize(n).map(|c| (c.clamp(-1.0, 1.0) * 127.0).round() as i8);
+    [x, y, z, 0]
+}
+
+/// The inverse of [`pack`], up to rounding.
+pub(crate) fn unpack([x, y, z, _]: [i8; 4]) -> [f32; 3] {
+    [x, y, z].map(|c| (c as f32 / 127.0).max(-1.0))
+}
+
+/// Returns `v` scaled to unit length, or `v` itself if it has zero length.
+pub(crate) fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = dot(v, v).sqrt();
+    if length > 0.0 {
+        scale(v, 1.0 / length)
+    } else {
+        v
+    }
+}
+
+/// Estimates `[dh/dx, dh/dz]` at `p` by the given `mode`. Gradients are only evaluated inside `[imin, imax]`.
+pub(crate) fn gradient<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    imin: [u32; 2],
+    imax: [u32; 2],
+    mode: &NormalMode,
+    p: [u32; 2],
+) -> [f32; 2] {
+    match *mode {
+        NormalMode::CentralDifference => central_difference(height_map, map_shape, p),
+        NormalMode::Smoothed { radius } => {
+            let r = radius as i64;
+            let sigma = (radius as f32 / 2.0).max(0.5);
+            let denom = 2.0 * sigma * sigma;
+            let mut sum = [0.0; 2];
+            let mut total_weight = 0.0;
+            for dz in -r..=r {
+                for dx in -r..=r {
+                    let q = [
+                        (p[0] as i64 + dx).clamp(imin[0] as i64, imax[0] as i64) as u32,
+                        (p[1] as i64 + dz).clamp(imin[1] as i64, imax[1] as i64) as u32,
+                    ];
+                    let weight = (-((dx * dx + dz * dz) as f32) / denom).exp();
+                    let [gx, gz] = central_difference(height_map, map_shape, q);
+                    sum[0] += weight * gx;
+                    sum[1] += weight * gz;
+                    total_weight += weight;
+                }
+            }
+            [sum[0] / total_weight, sum[1] / total_weight]
+        }
+        NormalMode::Sobel => sobel(height_map, map_shape, p),
+    }
+}
+
+fn central_difference<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    [x, z]: [u32; 2],
+) -> [f32; 2] {
+    let l = height_map.height(map_shape.linearize([x - 1, z]) as usize);
+    let r = height_map.height(map_shape.linearize([x + 1, z]) as usize);
+    let b = height_map.height(map_shape.linearize([x, z - 1]) as usize);
+    let t = height_map.height(map_shape.linearize([x, z + 1]) as usize);
+    [(r - l) / 2.0, (t - b) / 2.0]
+}
+
+fn sobel<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    [x, z]: [u32; 2],
+) -> [f32; 2] {
+    let h = |x: u32, z: u32| height_map.height(map_shape.linearize([x, z]) as usize);
+    let [bl, b, br] = [h(x - 1, z - 1), h(x, z - 1), h(x + 1, z - 1)];
+    let [l, r] = [h(x - 1, z), h(x + 1, z)];
+    let [tl, t, tr] = [h(x - 1, z + 1), h(x, z + 1), h(x + 1, z + 1)];
+    // Each kernel sums to 4 times the difference across 2 samples, so dividing by 8 gives the slope per sample.
+    [
+        ((tr + 2.0 * r + br) - (tl + 2.0 * l + bl)) / 8.0,
+        ((tl + 2.0 * t + tr) - (bl + 2.0 * b + br)) / 8.0,
+    ]
+}
@@ -0,0 +1,76 @@
+use crate::{push_grid_indices, CoarseMeshBuffer, HeightMapView};
+
+use ndshape::Shape;
+
+/// Generates a low-poly occluder mesh that never rises above the surface produced by [`height_mesh`](crate::height_mesh).
+///
+/// The interior of `[min, max]` is divided into coarse cells of `cell_size` samples per side (the last row and column of
+/// cells are truncated to fit). Each coarse vertex takes the minimum height of all samples in the coarse cells that touch
+/// it. Since every point of a coarse triangle is an interpolation of vertices that are no higher than the minimum sample of
+/// the enclosing coarse cell, the occluder is strictly conservative, which is what software occlusion culling requires.
+///
+/// The mesh covers exactly the same XZ footprint as the full-resolution mesh and uses the same triangle winding.
+pub fn occluder_mesh<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    cell_size: u32,
+    output: &mut CoarseMeshBuffer,
+) {
+    assert!(cell_size > 0);
+    assert!((map_shape.linearize(min) as usize) < height_map.len());
+    assert!((map_shape.linearize(max) as usize) < height_map.len());
+
+    output.reset();
+
+    let [minx, miny] = min;
+    let [maxx, maxy] = max;
+    let xs = coarse_lines(minx + 1, maxx - 1, cell_size);
+    let zs = coarse_lines(miny + 1, maxy - 1, cell_size);
+    let cells_x = xs.len().saturating_sub(1);
+    let cells_z = zs.len().saturating_sub(1);
+    if cells_x == 0 || cells_z == 0 {
+        return;
+    }
+
+    // The minimum sample of each coarse cell, including the samples on its border.
+    let mut cell_mins = Vec::with_capacity(cells_x * cells_z);
+    for cz in zs.windows(2) {
+        for cx in xs.windows(2) {
+            let mut cell_min = f32::INFINITY;
+            for z in cz[0]..=cz[1] {
+                for x in cx[0]..=cx[1] {
+                    cell_min =
+                        cell_min.min(height_map.height(map_shape.linearize([x, z]) as usize));
+                }
+            }
+            cell_mins.push(cell_min);
+        }
+    }
+
+    for (vz, &z) in zs.iter().enumerate() {
+        for (vx, &x) in xs.iter().enumerate() {
+            let mut y = f32::INFINITY;
+            for cz in vz.saturating_sub(1)..vz.min(cells_z - 1) + 1 {
+                for cx in vx.saturating_sub(1)..vx.min(cells_x - 1) + 1 {
+                    y = y.min(cell_mins[cz * cells_x + cx]);
+                }
+            }
+            output.positions.push([x as f32, y, z as f32]);
+        }
+    }
+
+    push_grid_indices(xs.len() as u32, zs.len() as u32, &mut output.indices);
+}
+
+/// The coordinates of coarse grid lines covering `[start, end]` with the given step, always including both endpoints. An
+/// empty range (`end < start`) has no lines.
+pub(crate) fn coarse_lines(start: u32, end: u32, step: u32) -> Vec<u32> {
+    if end < start {
+        return Vec::new();
+    }
+    let mut lines: Vec<u32> = (start..end).step_by(step as usize).collect();
+    lines.push(end);
+    lines
+}
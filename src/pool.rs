@@ -0,0 +1,60 @@
+use crate::HeightMeshBuffer;
+
+use std::sync::Mutex;
+
+/// Recycles [`HeightMeshBuffer`]s, so streaming worlds that mesh many chunks per frame reuse allocations instead of
+/// growing fresh buffers for every chunk.
+///
+/// The pool is internally synchronized, so it can be shared (e.g. in an `Arc`) by worker threads that mesh chunks in
+/// parallel.
+#[derive(Default)]
+pub struct BufferPool {
+    free: Mutex<Vec<HeightMeshBuffer>>,
+    max_free: Option<usize>,
+}
+
+impl BufferPool {
+    /// A pool that keeps at most `max_free` returned buffers, dropping any extra.
+    pub fn with_max_free(max_free: usize) -> Self {
+        Self {
+            free: Default::default(),
+            max_free: Some(max_free),
+        }
+    }
+
+    /// Takes a buffer for meshing a height map of `array_size` samples, or allocates a new one if the pool is empty.
+    ///
+    /// Buffers last used for the same extent are preferred, since their allocations already fit. Otherwise the buffer
+    /// with the most capacity is used. The buffer is reset and its [`HeightMeshBuffer::metadata`] cleared.
+    pub fn checkout(&self, array_size: usize) -> HeightMeshBuffer {
+        let mut buffer = {
+            let mut free = self.free.lock().unwrap();
+            let best = free
+                .iter()
+                .position(|b| b.stride_to_index.len() == array_size)
+                .or_else(|| (0..free.len()).max_by_key(|&i| free[i].positions.capacity()));
+            best.map(|i| free.swap_remove(i)).unwrap_or_default()
+        };
+        buffer.reset(array_size);
+        buffer.metadata = None;
+        buffer
+    }
+
+    /// Returns a buffer to the pool for reuse.
+    pub fn give_back(&self, buffer: HeightMeshBuffer) {
+        let mut free = self.free.lock().unwrap();
+        if self.max_free.is_none_or(|max| free.len() < max) {
+            free.push(buffer);
+        }
+    }
+
+    /// The number of buffers waiting to be reused.
+    pub fn num_free(&self) -> usize {
+        self.free.lock().unwrap().len()
+    }
+
+    /// Drops all of the free buffers.
+    pub fn clear(&self) {
+        self.free.lock().unwrap().clear();
+    }
+}
@@ -0,0 +1,176 @@
+//! Seeded generators for complete height maps of common terrain archetypes, for tests, demos, and prototyping.
+//!
+//! ```
+//! use height_mesh::ndshape::{ConstShape, ConstShape2u32};
+//! use height_mesh::presets::{generate, Preset};
+//! use height_mesh::{height_mesh, HeightMeshBuffer};
+//!
+//! type ChunkShape = ConstShape2u32<66, 66>;
+//!
+//! let height_map = generate(Preset::Island, 7, [66, 66]);
+//! let mut buffer = HeightMeshBuffer::default();
+//! height_mesh(&height_map, &ChunkShape {}, [0; 2], [65; 2], &mut buffer);
+//! assert!(!buffer.indices.is_empty());
+//! ```
+
+/// A terrain archetype for [`generate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    /// A landmass that falls off to sea level toward the edges of the map.
+    Island,
+    /// A terraced plateau cut by a meandering, steep-walled canyon.
+    Canyon,
+    /// Asymmetric, wind-aligned sand dunes.
+    Dunes,
+    /// A ridged mountain range running across the map.
+    MountainRange,
+}
+
+/// Generates a height map of `size` samples (including any boundary padding) with heights in `[0, 1]`.
+///
+/// Samples are stored row-major with X varying fastest, which is the layout of [`ndshape::ConstShape2u32`] and
+/// [`ndshape::Shape2u32`] of the same size. The same `preset`, `seed`, and `size` always produce the same map. Scale the
+/// heights with [`HeightMeshConfig::vertical_scale`](crate::HeightMeshConfig::vertical_scale) to taste.
+pub fn generate(preset: Preset, seed: u64, size: [u32; 2]) -> Vec<f32> {
+    let [w, h] = size;
+    let noise = Noise { seed };
+    // Features are sized relative to the map, so the same preset looks similar at any resolution.
+    let scale = 1.0 / w.max(h).max(1) as f32;
+
+    let mut map = Vec::with_capacity(w as usize * h as usize);
+    for y in 0..h {
+        for x in 0..w {
+            // Normalized coordinates in [0, 1].
+            let u = x as f32 * scale;
+            let v = y as f32 * scale;
+            let height = match preset {
+                Preset::Island => island(&noise, u, v, w as f32 * scale, h as f32 * scale),
+                Preset::Canyon => canyon(&noise, u, v),
+                Preset::Dunes => dunes(&noise, u, v),
+                Preset::MountainRange => mountain_range(&noise, u, v, h as f32 * scale),
+            };
+            map.push(height.clamp(0.0, 1.0));
+        }
+    }
+    map
+}
+
+fn island(noise: &Noise, u: f32, v: f32, extent_u: f32, extent_v: f32) -> f32 {
+    // Squared distance from the center, normalized so the edges are at 1.
+    let du = 2.0 * u / extent_u - 1.0;
+    let dv = 2.0 * v / extent_v - 1.0;
+    let falloff = 1.0 - (du * du + dv * dv).min(1.0);
+    let land = noise.fbm(4.0 * u, 4.0 * v, 5);
+    (land * falloff * 1.6 - 0.15).max(0.0)
+}
+
+fn canyon(noise: &Noise, u: f32, v: f32) -> f32 {
+    let plateau = 0.7 + 0.2 * noise.fbm(3.0 * u, 3.0 * v, 4);
+    // Terraces from quantizing the plateau.
+    let steps = 6.0;
+    let terraced =
+        (plateau * steps).floor() / steps + 0.3 * (plateau * steps).fract().powi(4) / steps;
+
+    // The canyon follows a zero crossing of a low-frequency, warped noise field.
+    let warp = noise.fbm(2.0 * u + 17.0, 2.0 * v - 5.0, 2);
+    let river = (2.0 * noise.fbm(1.5 * u + warp, 1.5 * v, 3) - 1.0).abs();
+    let width = 0.08;
+    let carve = smoothstep(0.0, width, river);
+    terraced * (0.15 + 0.85 * carve)
+}
+
+fn dunes(noise: &Noise, u: f32, v: f32) -> f32 {
+    let warp = noise.fbm(3.0 * u, 3.0 * v, 3);
+    // Waves perpendicular to the wind (+X), skewed so the windward slope is gentle and the slip face is steep.
+    let phase = (8.0 * u + 1.5 * v + 2.0 * warp).fract();
+    let profile = if phase < 0.8 {
+        phase / 0.8
+    } else {
+        (1.0 - phase) / 0.2
+    };
+    let amplitude = 0.4 + 0.4 * noise.fbm(2.0 * u + 31.0, 2.0 * v + 7.0, 2);
+    0.2 + amplitude * smoothstep(0.0, 1.0, profile) * 0.6
+}
+
+fn mountain_range(noise: &Noise, u: f32, v: f32, extent_v: f32) -> f32 {
+    // The range runs along X, meandering around the middle of the map.
+    let center = 0.5 * extent_v + 0.15 * (noise.fbm(2.0 * u, 0.0, 2) - 0.5);
+    let distance = (v - center).abs() / (0.5 * extent_v);
+    let mask = 1.0 - smoothstep(0.1, 0.8, distance);
+
+    // Ridged multifractal noise.
+    let mut sum = 0.0;
+    let mut amplitude = 0.5;
+    let mut frequency = 4.0;
+    let mut weight = 1.0;
+    for octave in 0..6 {
+        let n = 1.0 - (2.0 * noise.value(frequency * u, frequency * v, octave) - 1.0).abs();
+        let ridge = n * n * weight;
+        weight = (ridge * 2.0).clamp(0.0, 1.0);
+        sum += ridge * amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+
+    0.05 + 0.1 * noise.fbm(6.0 * u, 6.0 * v, 3) + 1.5 * mask * sum
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Seeded 2D value noise.
+struct Noise {
+    seed: u64,
+}
+
+impl Noise {
+    /// Fractal Brownian motion in roughly `[0, 1]`.
+    fn fbm(&self, x: f32, y: f32, octaves: u32) -> f32 {
+        let mut sum = 0.0;
+        let mut amplitude = 0.5;
+        let mut frequency = 1.0;
+        let mut total = 0.0;
+        for octave in 0..octaves {
+            sum += amplitude * self.value(frequency * x, frequency * y, octave);
+            total += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        sum / total
+    }
+
+    /// Smoothly interpolated lattice noise in `[0, 1]`. Each `layer` is independent.
+    fn value(&self, x: f32, y: f32, layer: u32) -> f32 {
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let tx = x - x0;
+        let ty = y - y0;
+        let (ix, iy) = (x0 as i64, y0 as i64);
+
+        let sx = tx * tx * (3.0 - 2.0 * tx);
+        let sy = ty * ty * (3.0 - 2.0 * ty);
+        let a = self.lattice(ix, iy, layer);
+        let b = self.lattice(ix + 1, iy, layer);
+        let c = self.lattice(ix, iy + 1, layer);
+        let d = self.lattice(ix + 1, iy + 1, layer);
+        let top = a + sx * (b - a);
+        let bottom = c + sx * (d - c);
+        top + sy * (bottom - top)
+    }
+
+    fn lattice(&self, x: i64, y: i64, layer: u32) -> f32 {
+        let mut h = self.seed ^ (layer as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        h = splitmix64(h ^ (x as u64));
+        h = splitmix64(h ^ (y as u64));
+        (h >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+fn splitmix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
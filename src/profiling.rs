@@ -0,0 +1,96 @@
+use crate::HeightMeshBuffer;
+
+#[cfg(feature = "profiling")]
+use std::time::{Duration, Instant};
+
+/// How long each stage of [`height_mesh_with_config`](crate::height_mesh_with_config) took, written to
+/// [`HeightMeshBuffer::timings`](crate::HeightMeshBuffer::timings). Only available with the `profiling` feature.
+///
+/// Stages that are disabled by the config take (almost) no time.
+#[cfg(feature = "profiling")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MeshTimings {
+    /// Hashing the inputs for [`HeightMeshBuffer::content_hash`](crate::HeightMeshBuffer::content_hash), if
+    /// [`HeightMeshConfig::content_hash`](crate::HeightMeshConfig::content_hash) is set.
+    pub content_hash: Duration,
+    /// The vertex pass, which also computes normals and curvature.
+    pub vertices: Duration,
+    /// The ambient occlusion pass.
+    pub ambient_occlusion: Duration,
+    /// Texture and lightmap coordinates.
+    pub uvs: Duration,
+    /// The shadow caster mesh and the LOD index buffers.
+    pub coarse_indices: Duration,
+    /// The index pass, including triangle materials and line indices.
+    pub indices: Duration,
+    /// Post passes over the finished mesh: tessellation and slope partitioning.
+    pub post: Duration,
+    /// The whole call.
+    pub total: Duration,
+}
+
+/// A stage of the mesher, for [`Stopwatch::lap`].
+#[derive(Clone, Copy)]
+pub(crate) enum Stage {
+    ContentHash,
+    Vertices,
+    AmbientOcclusion,
+    Uvs,
+    CoarseIndices,
+    Indices,
+    Post,
+}
+
+/// Records [`MeshTimings`] with the `profiling` feature, and does nothing without it.
+pub(crate) struct Stopwatch {
+    #[cfg(feature = "profiling")]
+    start: Instant,
+    #[cfg(feature = "profiling")]
+    last: Instant,
+}
+
+impl Stopwatch {
+    #[inline]
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(feature = "profiling")]
+            start: Instant::now(),
+            #[cfg(feature = "profiling")]
+            last: Instant::now(),
+        }
+    }
+
+    /// Records the time since the last lap as the duration of `stage`.
+    #[inline]
+    pub(crate) fn lap(&mut self, output: &mut HeightMeshBuffer, stage: Stage) {
+        #[cfg(feature = "profiling")]
+        {
+            let now = Instant::now();
+            let elapsed = now - self.last;
+            self.last = now;
+            let timings = &mut output.timings;
+            *match stage {
+                Stage::ContentHash => &mut timings.content_hash,
+                Stage::Vertices => &mut timings.vertices,
+                Stage::AmbientOcclusion => &mut timings.ambient_occlusion,
+                Stage::Uvs => &mut timings.uvs,
+                Stage::CoarseIndices => &mut timings.coarse_indices,
+                Stage::Indices => &mut timings.indices,
+                Stage::Post => &mut timings.post,
+            } = elapsed;
+        }
+        #[cfg(not(feature = "profiling"))]
+        let _ = (output, stage);
+    }
+
+    /// Records the time since [`Stopwatch::start`] as the total.
+    #[inline]
+    pub(crate) fn finish(self, output: &mut HeightMeshBuffer) {
+        #[cfg(feature = "profiling")]
+        {
+            output.timings.total = self.start.elapsed();
+        }
+        #[cfg(not(feature = "profiling"))]
+        let _ = output;
+    }
+}
@@ -0,0 +1,165 @@
+//! Import and export of headerless 16-bit `.raw`/`.r16` height maps, as used by Unity and Unreal.
+//!
+//! These files store one unsigned 16-bit integer per sample and nothing else, so the dimensions and the real height range
+//! must travel separately. [`Raw16Metadata`] records them, and can be saved as a small text sidecar next to the file.
+
+use crate::HeightMapView;
+
+use std::io::{self, BufRead, Read, Write};
+
+/// The byte order of 16-bit samples. Unity calls these "Windows" (little) and "Mac" (big); Unreal uses little-endian.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    #[default]
+    Little,
+    Big,
+}
+
+/// Everything needed to interpret a RAW16 file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Raw16Metadata {
+    /// The dimensions of the map in samples. Samples are row-major with X varying fastest.
+    pub size: [u32; 2],
+    /// The height represented by the value 0.
+    pub min_height: f32,
+    /// The height represented by the value 65535.
+    pub max_height: f32,
+    /// The byte order of each sample.
+    pub byte_order: ByteOrder,
+}
+
+impl Raw16Metadata {
+    /// Writes the metadata as `key=value` lines.
+    pub fn write_sidecar<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "width={}", self.size[0])?;
+        writeln!(writer, "height={}", self.size[1])?;
+        writeln!(writer, "min_height={}", self.min_height)?;
+        writeln!(writer, "max_height={}", self.max_height)?;
+        let order = match self.byte_order {
+            ByteOrder::Little => "little",
+            ByteOrder::Big => "big",
+        };
+        writeln!(writer, "byte_order={}", order)?;
+        writer.flush()
+    }
+
+    /// Reads metadata written by [`Raw16Metadata::write_sidecar`]. Unknown keys are ignored.
+    pub fn read_sidecar<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut width = None;
+        let mut height = None;
+        let mut min_height = None;
+        let mut max_height = None;
+        let mut byte_order = ByteOrder::Little;
+        for line in reader.lines() {
+            let line = line?;
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "width" => width = Some(parse(value)?),
+                "height" => height = Some(parse(value)?),
+                "min_height" => min_height = Some(parse(value)?),
+                "max_height" => max_height = Some(parse(value)?),
+                "byte_order" => {
+                    byte_order = match value {
+                        "little" => ByteOrder::Little,
+                        "big" => ByteOrder::Big,
+                        _ => return Err(invalid_data(format!("Unknown byte order {:?}", value))),
+                    }
+                }
+                _ => {}
+            }
+        }
+        let missing = |key| invalid_data(format!("Missing {:?} in RAW16 sidecar", key));
+        Ok(Self {
+            size: [
+                width.ok_or_else(|| missing("width"))?,
+                height.ok_or_else(|| missing("height"))?,
+            ],
+            min_height: min_height.ok_or_else(|| missing("min_height"))?,
+            max_height: max_height.ok_or_else(|| missing("max_height"))?,
+            byte_order,
+        })
+    }
+}
+
+/// Reads a RAW16 height map described by `metadata`, mapping the 16-bit range linearly onto `[min_height, max_height]`.
+pub fn read_raw16<R: Read>(mut reader: R, metadata: &Raw16Metadata) -> io::Result<Vec<f32>> {
+    let num_samples = metadata.size[0] as usize * metadata.size[1] as usize;
+    let mut bytes = vec![0; 2 * num_samples];
+    reader.read_exact(&mut bytes)?;
+
+    let range = metadata.max_height - metadata.min_height;
+    Ok(bytes
+        .chunks_exact(2)
+        .map(|b| {
+            let value = match metadata.byte_order {
+                ByteOrder::Little => u16::from_le_bytes([b[0], b[1]]),
+                ByteOrder::Big => u16::from_be_bytes([b[0], b[1]]),
+            };
+            metadata.min_height + range * (value as f32 / u16::MAX as f32)
+        })
+        .collect())
+}
+
+/// Writes every sample of `height_map` as RAW16, rescaling the map's own height range to the full 16-bit range for maximum
+/// precision.
+///
+/// Returns the metadata needed to read the file back (and to set the terrain height in Unity or the Z scale in Unreal).
+/// `size` must match the number of samples in the view.
+pub fn write_raw16<V: HeightMapView + ?Sized, W: Write>(
+    height_map: &V,
+    size: [u32; 2],
+    byte_order: ByteOrder,
+    mut writer: W,
+) -> io::Result<Raw16Metadata> {
+    let num_samples = size[0] as usize * size[1] as usize;
+    assert_eq!(num_samples, height_map.len());
+
+    let mut min_height = f32::INFINITY;
+    let mut max_height = f32::NEG_INFINITY;
+    for i in 0..num_samples {
+        let h = height_map.height(i);
+        min_height = min_height.min(h);
+        max_height = max_height.max(h);
+    }
+    if num_samples == 0 {
+        min_height = 0.0;
+        max_height = 0.0;
+    }
+
+    let range = max_height - min_height;
+    let scale = if range > 0.0 {
+        u16::MAX as f32 / range
+    } else {
+        0.0
+    };
+    let mut bytes = Vec::with_capacity(2 * num_samples);
+    for i in 0..num_samples {
+        let value = ((height_map.height(i) - min_height) * scale).round() as u16;
+        match byte_order {
+            ByteOrder::Little => bytes.extend_from_slice(&value.to_le_bytes()),
+            ByteOrder::Big => bytes.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+    writer.write_all(&bytes)?;
+    writer.flush()?;
+
+    Ok(Raw16Metadata {
+        size,
+        min_height,
+        max_height,
+        byte_order,
+    })
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> io::Result<T> {
+    value
+        .parse()
+        .map_err(|_| invalid_data(format!("Invalid value {:?} in RAW16 sidecar", value)))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
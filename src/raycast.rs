@@ -0,0 +1,80 @@
+use crate::normals::{add, cross, dot, scale, sub};
+use crate::HeightMeshBuffer;
+
+/// The nearest intersection found by [`HeightMeshBuffer::raycast`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    /// The index of the hit triangle, i.e. its vertices are `indices[3 * triangle..3 * triangle + 3]`.
+    pub triangle: u32,
+    /// The distance along the ray to the hit, in multiples of the length of `direction`.
+    pub distance: f32,
+    /// The weights of the triangle's three vertices at the hit point, which sum to 1.
+    pub barycentric: [f32; 3],
+    /// The normalized vertex normal, interpolated at the hit point like a renderer would. If the mesh has no normals, this
+    /// is the normal of the triangle instead.
+    pub normal: [f32; 3],
+}
+
+impl HeightMeshBuffer {
+    /// Finds the nearest triangle hit by the ray from `origin` along `direction`.
+    ///
+    /// This tests the generated triangles themselves, so the hit always lies exactly on the rendered surface, even after the
+    /// buffer has been edited. Both sides of each triangle are hit. Every triangle is tested, so for repeated queries on a
+    /// large mesh, prefer narrowing down the candidate region first.
+    pub fn raycast(&self, origin: [f32; 3], direction: [f32; 3]) -> Option<RayHit> {
+        let mut nearest: Option<RayHit> = None;
+
+        // Möller-Trumbore intersection.
+        for (triangle, tri) in self.indices.chunks_exact(3).enumerate() {
+            let [a, b, c] = [tri[0] as usize, tri[1] as usize, tri[2] as usize];
+            let p0 = self.positions[a];
+            let e1 = sub(self.positions[b], p0);
+            let e2 = sub(self.positions[c], p0);
+
+            let p = cross(direction, e2);
+            let det = dot(e1, p);
+            if det.abs() < f32::EPSILON {
+                // The ray is parallel to the triangle.
+                continue;
+            }
+            let inv_det = 1.0 / det;
+
+            let s = sub(origin, p0);
+            let u = dot(s, p) * inv_det;
+            if !(0.0..=1.0).contains(&u) {
+                continue;
+            }
+            let q = cross(s, e1);
+            let v = dot(direction, q) * inv_det;
+            if v < 0.0 || u + v > 1.0 {
+                continue;
+            }
+            let t = dot(e2, q) * inv_det;
+            if t < 0.0 || nearest.is_some_and(|hit| hit.distance <= t) {
+                continue;
+            }
+
+            let w = 1.0 - u - v;
+            let mut normal = match [a, b, c].map(|i| self.normal(i)) {
+                [Some(na), Some(nb), Some(nc)] => {
+                    add(add(scale(na, w), scale(nb, u)), scale(nc, v))
+                }
+                // Without vertex normals, fall back to the face normal.
+                _ => cross(e1, e2),
+            };
+            let length = dot(normal, normal).sqrt();
+            if length > 0.0 {
+                normal = scale(normal, 1.0 / length);
+            }
+
+            nearest = Some(RayHit {
+                triangle: triangle as u32,
+                distance: t,
+                barycentric: [w, u, v],
+                normal,
+            });
+        }
+
+        nearest
+    }
+}
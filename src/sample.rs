@@ -0,0 +1,177 @@
+use crate::detail::{self, Composited};
+use crate::normals::normalize;
+use crate::{vertex_normal, HeightMapView, HeightMeshConfig};
+
+use ndshape::Shape;
+
+/// Bilinearly interpolates the height map at a fractional position, which must lie within the map.
+pub(crate) fn bilinear<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    [x, z]: [f32; 2],
+) -> f32 {
+    let x0 = x.floor();
+    let z0 = z.floor();
+    let tx = x - x0;
+    let tz = z - z0;
+    let x0 = x0 as u32;
+    let z0 = z0 as u32;
+    // Don't step past the last sample when the position lies exactly on the far edge.
+    let x1 = if tx > 0.0 { x0 + 1 } else { x0 };
+    let z1 = if tz > 0.0 { z0 + 1 } else { z0 };
+
+    let h00 = height_map.height(map_shape.linearize([x0, z0]) as usize);
+    let h10 = height_map.height(map_shape.linearize([x1, z0]) as usize);
+    let h01 = height_map.height(map_shape.linearize([x0, z1]) as usize);
+    let h11 = height_map.height(map_shape.linearize([x1, z1]) as usize);
+
+    let h0 = h00 + tx * (h10 - h00);
+    let h1 = h01 + tx * (h11 - h01);
+    h0 + tz * (h1 - h0)
+}
+
+/// Samples the height map along `polyline` every `step` units of distance, returning `[distance, height]` pairs for route
+/// profiles and cross-sections.
+///
+/// Distances are measured along the polyline from its first point, in sample units. The first and last points are always
+/// included, and heights are bilinearly interpolated between samples. Every point of `polyline` must lie within the map.
+pub fn elevation_profile<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    polyline: &[[f32; 2]],
+    step: f32,
+) -> Vec<[f32; 2]> {
+    assert!(step > 0.0);
+
+    let mut profile = Vec::new();
+    let Some(&first) = polyline.first() else {
+        return profile;
+    };
+    profile.push([0.0, bilinear(height_map, map_shape, first)]);
+
+    // The distance at the start of the current segment, and the next distance to sample.
+    let mut segment_start = 0.0;
+    let mut next = step;
+    for segment in polyline.windows(2) {
+        let [[ax, az], [bx, bz]] = [segment[0], segment[1]];
+        let length = (bx - ax).hypot(bz - az);
+        let segment_end = segment_start + length;
+        while next < segment_end {
+            let t = (next - segment_start) / length;
+            let p = [ax + t * (bx - ax), az + t * (bz - az)];
+            profile.push([next, bilinear(height_map, map_shape, p)]);
+            next += step;
+        }
+        segment_start = segment_end;
+    }
+
+    if polyline.len() > 1 {
+        let last = polyline[polyline.len() - 1];
+        profile.push([segment_start, bilinear(height_map, map_shape, last)]);
+    }
+
+    profile
+}
+
+/// The surface of the mesh at one point, from [`sample_surface`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SurfaceSample {
+    /// The height of the surface, including [`HeightMeshConfig::vertical_scale`].
+    pub height: f32,
+    /// The unit normal, interpolated from the vertex normals as a renderer would.
+    pub normal: [f32; 3],
+    /// The angle between `normal` and +Y in radians, comparable with
+    /// [`HeightMeshConfig::walkable_slope`](crate::HeightMeshConfig::walkable_slope).
+    pub slope: f32,
+}
+
+/// Samples the surface of the mesh from [`height_mesh_with_config`](crate::height_mesh_with_config) at the map position
+/// `[x, z]`, so character controllers can align to exactly the surface that is rendered.
+///
+/// Unlike bilinear interpolation of the heights, this finds the triangle containing the point (respecting
+/// [`HeightMeshConfig::diagonals`]) and interpolates its vertex heights and normals linearly, which is what the rasterizer
+/// does. `min` and `max` must be the ones the mesh was generated with, and the point is clamped to the mesh. Like the
+/// mesher's input, `[x, z]` is in samples, even with [`HeightMeshConfig::geodetic`] spacing.
+pub fn sample_surface<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &HeightMeshConfig,
+    p: [f32; 2],
+) -> SurfaceSample {
+    match Composited::new(height_map, map_shape, config) {
+        Some(input) => sample(
+            &input,
+            map_shape,
+            min,
+            max,
+            &detail::remaining_config(config),
+            p,
+        ),
+        None => sample(height_map, map_shape, min, max, config, p),
+    }
+}
+
+fn sample<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &HeightMeshConfig,
+    [x, z]: [f32; 2],
+) -> SurfaceSample {
+    assert!(max[0] >= min[0] + 3 && max[1] >= min[1] + 3);
+    assert!((map_shape.linearize(max) as usize) < height_map.len());
+
+    let imin = [min[0] + 1, min[1] + 1];
+    let imax = [max[0] - 1, max[1] - 1];
+
+    // The quad containing the point, and the point's position within it.
+    let x = x.clamp(imin[0] as f32, imax[0] as f32);
+    let z = z.clamp(imin[1] as f32, imax[1] as f32);
+    let x0 = (x.floor() as u32).min(imax[0] - 1);
+    let z0 = (z.floor() as u32).min(imax[1] - 1);
+    let [tx, tz] = [x - x0 as f32, z - z0 as f32];
+
+    // Barycentric weights of the [bl, br, tl, tr] corners in the triangle containing the point.
+    let weights = if config.diagonals.is_flipped([x0, z0]) {
+        if tx + tz <= 1.0 {
+            [1.0 - tx - tz, tx, tz, 0.0]
+        } else {
+            [0.0, 1.0 - tz, 1.0 - tx, tx + tz - 1.0]
+        }
+    } else if tz >= tx {
+        [1.0 - tz, 0.0, tz - tx, tx]
+    } else {
+        [1.0 - tx, tx - tz, 0.0, tz]
+    };
+
+    let mut height = 0.0;
+    let mut normal = [0.0; 3];
+    for (corner, w) in [[x0, z0], [x0 + 1, z0], [x0, z0 + 1], [x0 + 1, z0 + 1]]
+        .into_iter()
+        .zip(weights)
+    {
+        if w == 0.0 {
+            continue;
+        }
+        let spacing = match &config.geodetic {
+            Some(geodetic) => geodetic.meters_per_sample(corner[1]).map(|d| d as f32),
+            None => [1.0; 2],
+        };
+        let h = height_map.height(map_shape.linearize(corner) as usize);
+        let n = vertex_normal(height_map, map_shape, [imin, imax], config, corner, spacing);
+        height += w * config.vertical_scale * h;
+        for i in 0..3 {
+            normal[i] += w * n[i];
+        }
+    }
+
+    let normal = normalize(normal);
+    SurfaceSample {
+        height,
+        normal,
+        slope: normal[1].clamp(-1.0, 1.0).acos(),
+    }
+}
@@ -0,0 +1,47 @@
+/// Which side of tile `a` that tile `b` is on, for [`blend_tile_seam`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileSide {
+    /// `b` is in the +X direction from `a`, so the last columns of `a` overlap the first columns of `b`.
+    PositiveX,
+    /// `b` is in the +Y direction from `a`, so the last rows of `a` overlap the first rows of `b`.
+    PositiveY,
+}
+
+/// Blends the `overlap` rows or columns that two adjacent source tiles share, so worlds assembled from independently
+/// generated tiles don't show ridges or steps along the tile boundaries.
+///
+/// Both tiles are row-major maps of `size` samples. Across the overlap, the blend fades linearly from the heights of `a`
+/// (on the side facing the interior of `a`) to the heights of `b`, and the result is written to both tiles, so they agree
+/// exactly on their shared samples. With an overlap of 1, the shared samples are simply averaged. Blend the seams before
+/// meshing, and before padding the chunks with their neighbors' samples.
+pub fn blend_tile_seam(a: &mut [f32], b: &mut [f32], size: [u32; 2], side: TileSide, overlap: u32) {
+    let [w, h] = size.map(|s| s as usize);
+    assert_eq!(a.len(), w * h);
+    assert_eq!(b.len(), w * h);
+    let overlap = overlap as usize;
+    let (across, along) = match side {
+        TileSide::PositiveX => (w, h),
+        TileSide::PositiveY => (h, w),
+    };
+    assert!(overlap <= across);
+
+    let index = |i: usize, j: usize| match side {
+        TileSide::PositiveX => j * w + i,
+        TileSide::PositiveY => i * w + j,
+    };
+    for k in 0..overlap {
+        // How far across the overlap this line is, from 0 on the `a` side to 1 on the `b` side.
+        let t = if overlap > 1 {
+            k as f32 / (overlap - 1) as f32
+        } else {
+            0.5
+        };
+        let ia = across - overlap + k;
+        for j in 0..along {
+            let (sa, sb) = (index(ia, j), index(k, j));
+            let blended = a[sa] + t * (b[sb] - a[sa]);
+            a[sa] = blended;
+            b[sb] = blended;
+        }
+    }
+}
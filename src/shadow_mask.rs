@@ -0,0 +1,99 @@
+use crate::sample::bilinear;
+use crate::HeightMapView;
+
+use ndshape::Shape;
+
+/// Parameters for [`sun_shadow_mask`].
+#[derive(Clone, Copy, Debug)]
+pub struct SunShadowConfig {
+    /// The direction pointing **toward** the sun, in the same `[x, y, z]` space as the mesh (+Y is up). Need not be
+    /// normalized.
+    pub sun_direction: [f32; 3],
+    /// When zero, the mask is binary. Larger values produce a soft penumbra that widens with distance from the occluder.
+    pub softness: f32,
+    /// Stop marching after this many samples of horizontal distance, if set. Unbounded rays are exact but cost more on large
+    /// maps.
+    pub max_distance: Option<f32>,
+}
+
+impl Default for SunShadowConfig {
+    fn default() -> Self {
+        Self {
+            sun_direction: [1.0, 1.0, 1.0],
+            softness: 0.0,
+            max_distance: None,
+        }
+    }
+}
+
+/// Bakes a per-sample sun visibility mask for every point in `[min, max]`, where 1 is fully lit and 0 is fully shadowed.
+///
+/// The mask is written row-major (X varies fastest) with dimensions `max - min + 1`, so it can be uploaded directly as a
+/// texture. It can also be used as a vertex attribute for the mesh from [`height_mesh`](crate::height_mesh) by skipping the
+/// boundary samples.
+///
+/// Each sample casts a ray toward the sun, stepping one sample horizontally at a time and comparing the ray height against
+/// the bilinearly interpolated terrain, until the ray leaves `[min, max]`. With a positive `softness`, the result is the
+/// smallest `softness * clearance / distance` along the ray, clamped to `[0, 1]`.
+pub fn sun_shadow_mask<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &SunShadowConfig,
+    mask: &mut Vec<f32>,
+) {
+    assert!((map_shape.linearize(max) as usize) < height_map.len());
+
+    mask.clear();
+
+    let [dx, dy, dz] = config.sun_direction;
+    let horizontal = (dx * dx + dz * dz).sqrt();
+    let (step_x, step_z, rise) = if horizontal > 0.0 {
+        (dx / horizontal, dz / horizontal, dy / horizontal)
+    } else {
+        (0.0, 0.0, f32::INFINITY)
+    };
+
+    let [minx, minz] = [min[0] as f32, min[1] as f32];
+    let [maxx, maxz] = [max[0] as f32, max[1] as f32];
+    let max_distance = config.max_distance.unwrap_or(f32::INFINITY);
+
+    for z in min[1]..=max[1] {
+        for x in min[0]..=max[0] {
+            if dy <= 0.0 {
+                // The sun is at or below the horizon.
+                mask.push(0.0);
+                continue;
+            }
+            if horizontal == 0.0 {
+                // The sun is directly overhead, so nothing can occlude it.
+                mask.push(1.0);
+                continue;
+            }
+
+            let h = height_map.height(map_shape.linearize([x, z]) as usize);
+            let mut visibility = 1.0f32;
+            let mut t = 1.0;
+            while t <= max_distance {
+                let px = x as f32 + t * step_x;
+                let pz = z as f32 + t * step_z;
+                if px < minx || px > maxx || pz < minz || pz > maxz {
+                    break;
+                }
+                let clearance = h + t * rise - bilinear(height_map, map_shape, [px, pz]);
+                if config.softness > 0.0 {
+                    visibility = visibility.min(config.softness * clearance / t);
+                    if visibility <= 0.0 {
+                        break;
+                    }
+                } else if clearance < 0.0 {
+                    visibility = 0.0;
+                    break;
+                }
+                t += 1.0;
+            }
+            mask.push(visibility.clamp(0.0, 1.0));
+        }
+    }
+}
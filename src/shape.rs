@@ -0,0 +1,84 @@
+use ndshape::Shape;
+
+/// A 2D shape whose dimensions are chosen at runtime, for when [`ConstShape2u32`](ndshape::ConstShape2u32) doesn't fit.
+///
+/// Unlike [`Shape2u32`](ndshape::Shape2u32), it remembers its dimensions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RuntimeShape2 {
+    dims: [u32; 2],
+}
+
+impl RuntimeShape2 {
+    /// A shape of `width` by `height` samples.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            dims: [width, height],
+        }
+    }
+
+    /// The `[width, height]` of the shape.
+    pub fn dims(&self) -> [u32; 2] {
+        self.dims
+    }
+
+    /// The largest sample coordinates, which is the `max` for meshing the whole map.
+    pub fn max(&self) -> [u32; 2] {
+        [self.dims[0] - 1, self.dims[1] - 1]
+    }
+}
+
+impl Shape<u32, 2> for RuntimeShape2 {
+    #[inline]
+    fn size(&self) -> u32 {
+        self.dims[0] * self.dims[1]
+    }
+
+    #[inline]
+    fn linearize(&self, [x, y]: [u32; 2]) -> u32 {
+        x + self.dims[0] * y
+    }
+
+    #[inline]
+    fn delinearize(&self, i: u32) -> [u32; 2] {
+        [i % self.dims[0], i / self.dims[0]]
+    }
+}
+
+/// The result of [`padded`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PaddedShape {
+    /// The shape of the whole height map, including the boundary padding.
+    pub shape: RuntimeShape2,
+    /// The `min` to pass to [`height_mesh`](crate::height_mesh), which is the first boundary sample.
+    pub min: [u32; 2],
+    /// The `max` to pass to [`height_mesh`](crate::height_mesh), which is the last boundary sample.
+    pub max: [u32; 2],
+    /// The first sample that becomes a mesh vertex.
+    pub interior_min: [u32; 2],
+    /// The last sample that becomes a mesh vertex.
+    pub interior_max: [u32; 2],
+}
+
+/// The height map shape and meshing extents for a chunk with `width` by `height` vertices, with the 1-sample boundary
+/// that [`height_mesh`](crate::height_mesh) needs for normals on every side.
+///
+/// ```
+/// use height_mesh::padded;
+///
+/// let padded = padded(64, 64);
+/// assert_eq!(padded.shape.dims(), [66, 66]);
+/// assert_eq!((padded.min, padded.max), ([0, 0], [65, 65]));
+/// assert_eq!((padded.interior_min, padded.interior_max), ([1, 1], [64, 64]));
+/// ```
+pub fn padded(width: u32, height: u32) -> PaddedShape {
+    assert!(width > 0 && height > 0);
+
+    let shape = RuntimeShape2::new(width + 2, height + 2);
+    PaddedShape {
+        shape,
+        min: [0; 2],
+        max: shape.max(),
+        interior_min: [1; 2],
+        interior_max: [width, height],
+    }
+}
@@ -0,0 +1,93 @@
+use crate::detail::{self, Composited};
+use crate::diagonals::quad_indices;
+use crate::normals::{cross, normalize, sub};
+use crate::{HeightMapView, HeightMeshConfig};
+
+use ndshape::Shape;
+
+/// A de-indexed triangle list with one normal per triangle, as CAD/CAM tools and some collision libraries expect.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TriangleSoup {
+    /// Three vertices per triangle, with the same winding as [`height_mesh`](crate::height_mesh).
+    pub positions: Vec<[f32; 3]>,
+    /// The unit face normal of each triangle.
+    pub normals: Vec<[f32; 3]>,
+}
+
+impl TriangleSoup {
+    /// The number of triangles.
+    pub fn len(&self) -> usize {
+        self.normals.len()
+    }
+
+    /// Returns true if there are no triangles.
+    pub fn is_empty(&self) -> bool {
+        self.normals.is_empty()
+    }
+}
+
+/// Generates the triangles of the mesh from [`height_mesh_with_config`](crate::height_mesh_with_config) directly as a
+/// [`TriangleSoup`], without building the indexed buffer first.
+///
+/// The vertex positions honor the height options of `config` and [`HeightMeshConfig::geodetic`]. Vertex normals, UVs,
+/// and the other per-vertex channels have no place in a soup, so the rest of `config` is ignored.
+pub fn triangle_soup<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &HeightMeshConfig,
+) -> TriangleSoup {
+    match Composited::new(height_map, map_shape, config) {
+        Some(input) => soup(
+            &input,
+            map_shape,
+            min,
+            max,
+            &detail::remaining_config(config),
+        ),
+        None => soup(height_map, map_shape, min, max, config),
+    }
+}
+
+fn soup<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    config: &HeightMeshConfig,
+) -> TriangleSoup {
+    assert!((map_shape.linearize(max) as usize) < height_map.len());
+
+    let position = |[x, z]: [u32; 2]| {
+        let y = config.vertical_scale * height_map.height(map_shape.linearize([x, z]) as usize);
+        let [px, pz] = match &config.geodetic {
+            Some(geodetic) => geodetic.position([x, z]).map(|p| p as f32),
+            None => [x as f32, z as f32],
+        };
+        [px, y, pz]
+    };
+
+    // Quads have their bottom-left corner in [min + 1, max - 2].
+    let num_cells =
+        (max[0].saturating_sub(min[0] + 2) * max[1].saturating_sub(min[1] + 2)) as usize;
+    let mut soup = TriangleSoup {
+        positions: Vec::with_capacity(6 * num_cells),
+        normals: Vec::with_capacity(2 * num_cells),
+    };
+    for z in min[1] + 1..max[1].saturating_sub(1) {
+        for x in min[0] + 1..max[0].saturating_sub(1) {
+            let bl = position([x, z]);
+            let br = position([x + 1, z]);
+            let tl = position([x, z + 1]);
+            let tr = position([x + 1, z + 1]);
+            let [a0, b0, c0, a1, b1, c1] =
+                quad_indices([bl, br, tl, tr], config.diagonals.is_flipped([x, z]));
+            for [a, b, c] in [[a0, b0, c0], [a1, b1, c1]] {
+                soup.positions.extend_from_slice(&[a, b, c]);
+                soup.normals.push(normalize(cross(sub(b, a), sub(c, a))));
+            }
+        }
+    }
+    soup
+}
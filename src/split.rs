@@ -0,0 +1,62 @@
+use crate::HeightMeshBuffer;
+
+/// One piece of a mesh split by [`HeightMeshBuffer::split_u16`].
+#[derive(Clone, Debug, Default)]
+pub struct U16Submesh {
+    /// The position of each submesh vertex.
+    pub positions: Vec<[f32; 3]>,
+    /// The normal of each submesh vertex, unpacked if the source has [`HeightMeshBuffer::packed_normals`]. Empty if the
+    /// source has no normals.
+    pub normals: Vec<[f32; 3]>,
+    /// Triangle indices, referring to offsets in the `positions` and `normals` vectors.
+    pub indices: Vec<u16>,
+    /// The index of each submesh vertex in the source buffer, for gathering any other vertex attributes.
+    pub source_vertices: Vec<u32>,
+}
+
+const MAX_U16_VERTICES: usize = 1 << 16;
+
+impl HeightMeshBuffer {
+    /// Splits the mesh into submeshes of at most 65,536 vertices each, with indices remapped to `u16`, for renderers and file
+    /// formats limited to 16-bit indices.
+    ///
+    /// Triangles are assigned to submeshes greedily in their original order, so the row-major order of the mesher keeps
+    /// each submesh a compact band of rows. Vertices on the border between two submeshes are duplicated.
+    pub fn split_u16(&self) -> Vec<U16Submesh> {
+        let mut submeshes = Vec::new();
+        let mut submesh = U16Submesh::default();
+        let mut local_index = vec![u32::MAX; self.positions.len()];
+
+        for tri in self.indices.chunks_exact(3) {
+            let new_vertices = tri
+                .iter()
+                .filter(|&&v| local_index[v as usize] == u32::MAX)
+                .count();
+            if submesh.source_vertices.len() + new_vertices > MAX_U16_VERTICES {
+                for &v in submesh.source_vertices.iter() {
+                    local_index[v as usize] = u32::MAX;
+                }
+                submeshes.push(std::mem::take(&mut submesh));
+            }
+
+            for &v in tri {
+                let local = &mut local_index[v as usize];
+                if *local == u32::MAX {
+                    *local = submesh.source_vertices.len() as u32;
+                    submesh.source_vertices.push(v);
+                    submesh.positions.push(self.positions[v as usize]);
+                    if let Some(normal) = self.normal(v as usize) {
+                        submesh.normals.push(normal);
+                    }
+                }
+                submesh.indices.push(*local as u16);
+            }
+        }
+
+        if !submesh.indices.is_empty() {
+            submeshes.push(submesh);
+        }
+
+        submeshes
+    }
+}
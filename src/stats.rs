@@ -0,0 +1,108 @@
+use crate::HeightMapView;
+
+use ndshape::Shape;
+
+/// Summary statistics of the heights in a region, from [`height_stats`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeightStats {
+    /// The lowest height.
+    pub min: f32,
+    /// The highest height.
+    pub max: f32,
+    /// The average height.
+    pub mean: f32,
+    /// The number of samples in each of the equal-width bins spanning `[min, max]`.
+    pub histogram: Vec<u32>,
+    /// The number of samples counted. Samples that aren't finite (like NaN no-data values) are skipped.
+    pub count: u32,
+}
+
+impl HeightStats {
+    /// The width of each histogram bin.
+    pub fn bin_width(&self) -> f32 {
+        (self.max - self.min) / self.histogram.len() as f32
+    }
+
+    /// Estimates the height below which `p` (in `[0, 1]`) of the samples lie, interpolating linearly within a histogram bin.
+    ///
+    /// The error is at most one bin width. For example, `percentile(0.05)` and `percentile(0.95)` give a range for color
+    /// ramps that ignores outliers.
+    pub fn percentile(&self, p: f32) -> f32 {
+        if self.count == 0 {
+            return f32::NAN;
+        }
+        let target = p.clamp(0.0, 1.0) * self.count as f32;
+        let mut below = 0.0;
+        for (i, &n) in self.histogram.iter().enumerate() {
+            let n = n as f32;
+            if n > 0.0 && below + n >= target {
+                let t = (target - below) / n;
+                return self.min + (i as f32 + t) * self.bin_width();
+            }
+            below += n;
+        }
+        self.max
+    }
+}
+
+/// Computes the min, max, mean, and a histogram of `num_bins` bins for the heights in `[min, max]`.
+///
+/// This takes two passes over the region and no sorting, so it's cheap enough to run per chunk for LOD error heuristics,
+/// auto-ranging color ramps, and sea level detection.
+pub fn height_stats<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    num_bins: usize,
+) -> HeightStats {
+    assert!(num_bins > 0);
+    assert!((map_shape.linearize(max) as usize) < height_map.len());
+
+    let heights = || {
+        (min[1]..=max[1])
+            .flat_map(move |z| (min[0]..=max[0]).map(move |x| [x, z]))
+            .map(|p| height_map.height(map_shape.linearize(p) as usize))
+            .filter(|h| h.is_finite())
+    };
+
+    let mut lo = f32::INFINITY;
+    let mut hi = f32::NEG_INFINITY;
+    let mut sum = 0.0f64;
+    let mut count = 0u32;
+    for h in heights() {
+        lo = lo.min(h);
+        hi = hi.max(h);
+        sum += h as f64;
+        count += 1;
+    }
+
+    let mut histogram = vec![0; num_bins];
+    if count == 0 {
+        return HeightStats {
+            min: f32::NAN,
+            max: f32::NAN,
+            mean: f32::NAN,
+            histogram,
+            count,
+        };
+    }
+
+    let scale = if hi > lo {
+        num_bins as f32 / (hi - lo)
+    } else {
+        0.0
+    };
+    for h in heights() {
+        let bin = (((h - lo) * scale) as usize).min(num_bins - 1);
+        histogram[bin] += 1;
+    }
+
+    HeightStats {
+        min: lo,
+        max: hi,
+        mean: (sum / count as f64) as f32,
+        histogram,
+        count,
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{HeightMapView, HeightMeshBuffer};
+
+/// Index and stride tables for meshing a whole `ConstShape2u32<X, Y>` chunk, computed at compile time, for
+/// [`height_mesh_with_tables`]. Requires the `const-tables` feature.
+///
+/// `V` and `N` must be the number of vertices and indices of the chunk mesh, which stable Rust can't compute from `X` and
+/// `Y` in a type. Use [`const_mesh_tables!`](crate::const_mesh_tables) to declare a `static` with the right sizes. The
+/// tables can only be built by [`ConstMeshTables::new`], so [`height_mesh_with_tables`] can trust them.
+pub struct ConstMeshTables<const X: u32, const Y: u32, const V: usize, const N: usize> {
+    vertex_strides: [u32; V],
+    indices: [u32; N],
+}
+
+impl<const X: u32, const Y: u32, const V: usize, const N: usize> ConstMeshTables<X, Y, V, N> {
+    /// Computes the tables. Panics (at compile time, when used for a `const` or `static`) if `V` or `N` is wrong.
+    pub const fn new() -> Self {
+        assert!(X >= 3 && Y >= 3);
+        assert!(V == num_table_vertices(X, Y), "wrong number of vertices");
+        assert!(N == num_table_indices(X, Y), "wrong number of indices");
+
+        let [w, h] = [X - 2, Y - 2];
+        let mut vertex_strides = [0; V];
+        let mut i = 0;
+        while i < V {
+            let [x, z] = [1 + i as u32 % w, 1 + i as u32 / w];
+            vertex_strides[i] = z * X + x;
+            i += 1;
+        }
+
+        let mut indices = [0; N];
+        let mut i = 0;
+        let mut cz = 0;
+        while cz + 1 < h {
+            let mut cx = 0;
+            while cx + 1 < w {
+                let bl = cz * w + cx;
+                let br = bl + 1;
+                let tl = bl + w;
+                let tr = tl + 1;
+                let quad = [bl, tl, tr, bl, tr, br];
+                let mut k = 0;
+                while k < 6 {
+                    indices[i + k] = quad[k];
+                    k += 1;
+                }
+                i += 6;
+                cx += 1;
+            }
+            cz += 1;
+        }
+
+        Self {
+            vertex_strides,
+            indices,
+        }
+    }
+
+    /// The map stride of each vertex, in vertex order.
+    pub const fn vertex_strides(&self) -> &[u32; V] {
+        &self.vertex_strides
+    }
+
+    /// The triangle indices, exactly as [`height_mesh`](crate::height_mesh) generates them.
+    pub const fn indices(&self) -> &[u32; N] {
+        &self.indices
+    }
+}
+
+impl<const X: u32, const Y: u32, const V: usize, const N: usize> Default
+    for ConstMeshTables<X, Y, V, N>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The number of vertices of the mesh of a whole `X` by `Y` chunk, for [`ConstMeshTables`].
+pub const fn num_table_vertices(x: u32, y: u32) -> usize {
+    (x as usize - 2) * (y as usize - 2)
+}
+
+/// The number of indices of the mesh of a whole `X` by `Y` chunk, for [`ConstMeshTables`].
+pub const fn num_table_indices(x: u32, y: u32) -> usize {
+    6 * (x as usize - 3) * (y as usize - 3)
+}
+
+/// Declares a `static` [`ConstMeshTables`] for a `ConstShape2u32<$x, $y>` chunk. Requires the `const-tables` feature.
+///
+/// ```
+/// height_mesh::const_mesh_tables!(static TABLES: 18, 18);
+///
+/// let heights = [1.0; 18 * 18];
+/// let mut buffer = height_mesh::HeightMeshBuffer::default();
+/// height_mesh::height_mesh_with_tables(&heights, &TABLES, &mut buffer);
+/// assert_eq!(buffer.indices.len(), 6 * 15 * 15);
+/// ```
+#[macro_export]
+macro_rules! const_mesh_tables {
+    ($vis:vis static $name:ident: $x:expr, $y:expr) => {
+        $vis static $name: $crate::ConstMeshTables<
+            { $x },
+            { $y },
+            { $crate::num_table_vertices($x, $y) },
+            { $crate::num_table_indices($x, $y) },
+        > = $crate::ConstMeshTables::new();
+    };
+}
+
+/// Generates the same mesh as [`height_mesh`](crate::height_mesh) for the whole of a `ConstShape2u32<X, Y>` chunk, but
+/// copies the vertex strides and indices from `tables` instead of computing them, which saves time when many chunks of
+/// the same fixed size are meshed. Requires the `const-tables` feature.
+///
+/// [`HeightMeshBuffer::content_hash`] is not computed, and is left at 0.
+pub fn height_mesh_with_tables<
+    M: HeightMapView + ?Sized,
+    const X: u32,
+    const Y: u32,
+    const V: usize,
+    const N: usize,
+>(
+    height_map: &M,
+    tables: &ConstMeshTables<X, Y, V, N>,
+    output: &mut HeightMeshBuffer,
+) {
+    assert!((X * Y) as usize <= height_map.len());
+
+    output.reset(height_map.len());
+
+    // Central differencing, as in the mesher.
+    let x_stride = 1;
+    let y_stride = X as usize;
+    let width = X - 2;
+    for (i, &stride) in tables.vertex_strides.iter().enumerate() {
+        let [x, z] = [1 + i as u32 % width, 1 + i as u32 / width];
+        let s = stride as usize;
+        output.stride_to_index[s] = i as u32;
+        output
+            .positions
+            .push([x as f32, height_map.height(s), z as f32]);
+
+        // SAFETY: `new` only produces strides of interior vertices of the chunk, which fits in the map.
+        let [l, r, b, t] = unsafe {
+            [
+                height_map.height_unchecked(s - x_stride),
+                height_map.height_unchecked(s + x_stride),
+                height_map.height_unchecked(s - y_stride),
+                height_map.height_unchecked(s + y_stride),
+            ]
+        };
+        output.normals.push([-(r - l) / 2.0, 1.0, -(t - b) / 2.0]);
+    }
+    output.indices.extend_from_slice(&tables.indices);
+}
@@ -0,0 +1,240 @@
+use crate::diagonals::quad_indices;
+use crate::normals::{self, dot, sub};
+use crate::HeightMeshBuffer;
+
+/// A per-vertex attribute that can be interpolated.
+trait Lerp: Copy {
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    #[inline]
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + t * (other - self)
+    }
+}
+
+impl<const N: usize> Lerp for [f32; N] {
+    #[inline]
+    fn lerp(self, other: Self, t: f32) -> Self {
+        std::array::from_fn(|i| self[i].lerp(other[i], t))
+    }
+}
+
+/// Pushes the bilinear interpolation of the `[bl, br, tl, tr]` vertex attributes at `[s, t]`, if the channel is present.
+fn push_bilinear<T: Lerp>(
+    channel: &mut Vec<T>,
+    num_vertices: usize,
+    corners: [usize; 4],
+    [s, t]: [f32; 2],
+) {
+    if channel.len() != num_vertices {
+        return;
+    }
+    let [bl, br, tl, tr] = corners.map(|i| channel[i]);
+    channel.push(bl.lerp(br, s).lerp(tl.lerp(tr, s), t));
+}
+
+/// Pushes the attribute of the corner of `[bl, br, tl, tr]` nearest to `[s, t]`, if the channel is present, for attributes
+/// that can't be interpolated.
+fn push_nearest<T: Copy>(
+    channel: &mut Vec<T>,
+    num_vertices: usize,
+    corners: [usize; 4],
+    [s, t]: [f32; 2],
+) {
+    if channel.len() != num_vertices {
+        return;
+    }
+    let corner = (s >= 0.5) as usize + 2 * (t >= 0.5) as usize;
+    channel.push(channel[corners[corner]]);
+}
+
+/// Subdivides the grid mesh in `output`, a row-major grid of `grid_size` vertices, so that no edge along X or Z is longer
+/// than `max_edge_length`.
+///
+/// Whole columns and rows of cells are subdivided together, so that subdivided cells always match their neighbors without
+/// T-junctions. The new vertices interpolate the attributes of their cell corners bilinearly, and the indices and triangle
+/// materials and cells are regenerated for the finer grid. The sub-cells of a cell are split along the same diagonal as
+/// the cell, which is flipped when `is_flipped([cx, cz])` is true for its bottom-left grid vertex.
+pub(crate) fn subdivide_long_edges(
+    grid_size: [u32; 2],
+    max_edge_length: f32,
+    is_flipped: impl Fn([u32; 2]) -> bool,
+    output: &mut HeightMeshBuffer,
+) {
+    assert!(max_edge_length > 0.0);
+
+    let [w, h] = grid_size.map(|s| s as usize);
+    if w < 2 || h < 2 {
+        return;
+    }
+    let index = |x: usize, z: usize| z * w + x;
+    let length = |a: usize, b: usize| {
+        let d = sub(output.positions[b], output.positions[a]);
+        dot(d, d).sqrt()
+    };
+    let splits = |len: f32| (len / max_edge_length).ceil().max(1.0) as usize;
+
+    // The number of sub-cells for each column and row of cells.
+    let column_splits: Vec<usize> = (0..w - 1)
+        .map(|x| {
+            (0..h)
+                .map(|z| splits(length(index(x, z), index(x + 1, z))))
+                .max()
+                .unwrap_or(1)
+        })
+        .collect();
+    let row_splits: Vec<usize> = (0..h - 1)
+        .map(|z| {
+            (0..w)
+                .map(|x| splits(length(index(x, z), index(x, z + 1))))
+                .max()
+                .unwrap_or(1)
+        })
+        .collect();
+    if column_splits
+        .iter()
+        .chain(row_splits.iter())
+        .all(|&n| n == 1)
+    {
+        return;
+    }
+
+    // Each fine grid line is a (cell, fraction) pair along its axis. The last line is the far edge of the last cell.
+    let fine_lines = |splits: &[usize]| {
+        let mut lines: Vec<(usize, usize, usize)> = splits
+            .iter()
+            .enumerate()
+            .flat_map(|(cell, &n)| (0..n).map(move |i| (cell, i, n)))
+            .collect();
+        lines.push((splits.len() - 1, 1, 1));
+        lines
+    };
+    let xs = fine_lines(&column_splits);
+    let zs = fine_lines(&row_splits);
+
+    let mut fine_index = Vec::with_capacity(xs.len() * zs.len());
+    for &(cz, iz, nz) in zs.iter() {
+        for &(cx, ix, nx) in xs.iter() {
+            // A fine line at fraction 0 (or 1, for the far edge) is an original grid line.
+            let original =
+                |cell: usize, i: usize, n: usize| (i == 0 || i == n).then(|| cell + i / n);
+            if let (Some(x), Some(z)) = (original(cx, ix, nx), original(cz, iz, nz)) {
+                fine_index.push(index(x, z) as u32);
+                continue;
+            }
+
+            let corners = [
+                index(cx, cz),
+                index(cx + 1, cz),
+                index(cx, cz + 1),
+                index(cx + 1, cz + 1),
+            ];
+            let st = [ix as f32 / nx as f32, iz as f32 / nz as f32];
+            let num_vertices = output.positions.len();
+            fine_index.push(num_vertices as u32);
+            push_bilinear(&mut output.positions, num_vertices, corners, st);
+            push_bilinear(&mut output.normals, num_vertices, corners, st);
+            if output.packed_normals.len() == num_vertices {
+                let [bl, br, tl, tr] = corners.map(|i| normals::unpack(output.packed_normals[i]));
+                let normal = bl.lerp(br, st[0]).lerp(tl.lerp(tr, st[0]), st[1]);
+                output.packed_normals.push(normals::pack(normal));
+            }
+            push_bilinear(&mut output.uvs, num_vertices, corners, st);
+            push_bilinear(&mut output.lightmap_uvs, num_vertices, corners, st);
+            push_bilinear(&mut output.colors, num_vertices, corners, st);
+            push_bilinear(&mut output.curvature, num_vertices, corners, st);
+            push_bilinear(&mut output.ambient_occlusion, num_vertices, corners, st);
+            push_nearest(&mut output.texture_layers, num_vertices, corners, st);
+            push_nearest(&mut output.texture_layer_weights, num_vertices, corners, st);
+        }
+    }
+
+    let materials = std::mem::take(&mut output.triangle_materials);
+    let cells = std::mem::take(&mut output.triangle_cells);
+    output.indices.clear();
+    let fine_w = xs.len();
+    for (fz, &(cz, _, _)) in zs[..zs.len() - 1].iter().enumerate() {
+        for (fx, &(cx, _, _)) in xs[..xs.len() - 1].iter().enumerate() {
+            let bl = fine_index[fz * fine_w + fx];
+            let br = fine_index[fz * fine_w + fx + 1];
+            let tl = fine_index[(fz + 1) * fine_w + fx];
+            let tr = fine_index[(fz + 1) * fine_w + fx + 1];
+            let flipped = is_flipped([cx as u32, cz as u32]);
+            output
+                .indices
+                .extend_from_slice(&quad_indices([bl, br, tl, tr], flipped));
+            if !materials.is_empty() {
+                let material = materials[2 * (cz * (w - 1) + cx)];
+                output.triangle_materials.extend_from_slice(&[material; 2]);
+            }
+            if !cells.is_empty() {
+                let cell = cells[2 * (cz * (w - 1) + cx)];
+                output.triangle_cells.extend_from_slice(&[cell; 2]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subdivides_the_columns_and_rows_of_a_long_edge() {
+        // A 3x3 grid of vertices with unit spacing, and one corner raised so that its two edges are about 3.16 long.
+        let positions: Vec<[f32; 3]> = (0..3)
+            .flat_map(|z| (0..3).map(move |x| [x, z]))
+            .map(|[x, z]| [x as f32, if [x, z] == [2, 0] { 3.0 } else { 0.0 }, z as f32])
+            .collect();
+        let mut output = HeightMeshBuffer {
+            positions: positions.clone(),
+            ..Default::default()
+        };
+        for cz in 0..2 {
+            for cx in 0..2 {
+                let [bl, br, tl, tr] = [[cx, cz], [cx + 1, cz], [cx, cz + 1], [cx + 1, cz + 1]]
+                    .map(|[x, z]| z * 3 + x);
+                output
+                    .indices
+                    .extend_from_slice(&quad_indices([bl, br, tl, tr], false));
+                output
+                    .triangle_materials
+                    .extend_from_slice(&[(cz * 2 + cx) as u8; 2]);
+            }
+        }
+
+        let max_edge_length = 1.5;
+        subdivide_long_edges([3, 3], max_edge_length, |_| false, &mut output);
+
+        // The second column and the first row of cells are each split in 3, for a 5x5 grid of vertices.
+        assert_eq!(output.positions.len(), 25);
+        assert_eq!(output.indices.len(), 4 * 4 * 6);
+        // The original vertices keep their indices, and are never duplicated.
+        assert_eq!(&output.positions[..9], &positions[..]);
+        assert!(output.positions[9..].iter().all(|p| !positions.contains(p)));
+        for v in 0..9 {
+            assert!(output.indices.contains(&v));
+        }
+
+        assert_eq!(output.triangle_materials.len(), output.indices.len() / 3);
+        for (triangle, &material) in output
+            .indices
+            .chunks_exact(3)
+            .zip(&output.triangle_materials)
+        {
+            let corners = [0, 1, 2].map(|k| output.positions[triangle[k] as usize]);
+            for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+                let d = sub(corners[b], corners[a]);
+                if d[0] == 0.0 || d[2] == 0.0 {
+                    assert!(dot(d, d).sqrt() <= max_edge_length);
+                }
+            }
+            // The centroid lies inside the parent cell.
+            let [cx, cz] =
+                [0, 2].map(|axis| (corners.iter().map(|p| p[axis]).sum::<f32>() / 3.0) as u8);
+            assert_eq!(material, cz * 2 + cx);
+        }
+    }
+}
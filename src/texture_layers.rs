@@ -0,0 +1,47 @@
+use crate::HeightMeshBuffer;
+
+use ndshape::Shape;
+use std::cmp::Reverse;
+
+/// Writes the texture array layers of each vertex in the row-major interior `[imin, imax]`, from the per-cell `layer_map`.
+///
+/// Each vertex looks at the four cells that touch it. The most common layer is the primary one, the second most common is
+/// the secondary one, and the weight is the share of the secondary layer among the two. Ties go to the lower layer index,
+/// so neighboring chunks always agree on their shared vertices.
+pub(crate) fn texture_layers<S: Shape<u32, 2>>(
+    layer_map: &[u8],
+    map_shape: &S,
+    imin: [u32; 2],
+    imax: [u32; 2],
+    output: &mut HeightMeshBuffer,
+) {
+    for z in imin[1]..=imax[1] {
+        for x in imin[0]..=imax[0] {
+            // Cells are identified by their bottom-left corner sample.
+            let mut cells = [[x - 1, z - 1], [x, z - 1], [x - 1, z], [x, z]]
+                .map(|cell| layer_map[map_shape.linearize(cell) as usize]);
+            cells.sort_unstable();
+
+            let mut runs = [(0, 0); 4];
+            let mut num_runs = 0;
+            for layer in cells {
+                if num_runs > 0 && runs[num_runs - 1].0 == layer {
+                    runs[num_runs - 1].1 += 1;
+                } else {
+                    runs[num_runs] = (layer, 1u32);
+                    num_runs += 1;
+                }
+            }
+            // Stable, so equal counts stay in ascending order of layer.
+            let counts = &mut runs[..num_runs];
+            counts.sort_by_key(|&(_, n)| Reverse(n));
+
+            let (primary, n1) = counts[0];
+            let (secondary, n2) = counts.get(1).copied().unwrap_or((primary, 0));
+            output.texture_layers.push([primary, secondary]);
+            output
+                .texture_layer_weights
+                .push(n2 as f32 / (n1 + n2) as f32);
+        }
+    }
+}
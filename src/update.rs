@@ -0,0 +1,181 @@
+use crate::detail::{self, Composited};
+use crate::normals;
+use crate::{
+    content_hash, partition_by_slope, shadow_caster_mesh, snap_to_lattice, vertex_curvature,
+    vertex_normal, HeightMapView, HeightMeshBuffer, HeightMeshConfig,
+};
+
+use ndshape::Shape;
+
+/// Updates a mesh in place after the heights in `[dirty_min, dirty_max]` have changed, for deformation effects like
+/// footprints and tire tracks where the topology never changes and a full remesh is wasteful.
+///
+/// `output` must have been generated by [`height_mesh_with_config`](crate::height_mesh_with_config) with the same
+/// `map_shape`, `min`, `max`, and `config`. The heights of the vertices in the dirty rectangle are rewritten, along with the
+/// normals (and curvature, if enabled) of every vertex whose normal could depend on them. The shadow caster, the
+/// walkable and steep triangles, and the content hash are regenerated if the config asks for them. The indices and every
+/// other channel are left as they are; in particular, ambient occlusion is not updated.
+///
+/// # Panics
+///
+/// Panics if [`HeightMeshConfig::max_edge_length`] is set, since the tessellated vertices are not on the sample grid.
+#[allow(clippy::too_many_arguments)]
+pub fn update_height_mesh_region<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    dirty_min: [u32; 2],
+    dirty_max: [u32; 2],
+    config: &HeightMeshConfig,
+    output: &mut HeightMeshBuffer,
+) {
+    assert!(
+        config.max_edge_length.is_none(),
+        "tessellated meshes can't be updated in place"
+    );
+
+    let (min_max, dirty) = ([min, max], [dirty_min, dirty_max]);
+    match Composited::new(height_map, map_shape, config) {
+        Some(input) => {
+            let config = detail::remaining_config(config);
+            update(&input, map_shape, min_max, dirty, &config, output)
+        }
+        None => update(height_map, map_shape, min_max, dirty, config, output),
+    }
+
+    output.content_hash = if config.content_hash {
+        content_hash(height_map, map_shape, min, max, config)
+    } else {
+        0
+    };
+}
+
+fn update<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    [min, max]: [[u32; 2]; 2],
+    [dirty_min, dirty_max]: [[u32; 2]; 2],
+    config: &HeightMeshConfig,
+    output: &mut HeightMeshBuffer,
+) {
+    // SAFETY
+    // Check the bounds on the array before we start using get_unchecked.
+    assert!((map_shape.linearize(min) as usize) < height_map.len());
+    assert!((map_shape.linearize(max) as usize) < height_map.len());
+
+    let imin = [min[0] + 1, min[1] + 1];
+    let imax = [max[0] - 1, max[1] - 1];
+
+    // Positions only change where the heights did.
+    for z in dirty_min[1].max(imin[1])..=dirty_max[1].min(imax[1]) {
+        for x in dirty_min[0].max(imin[0])..=dirty_max[0].min(imax[0]) {
+            let stride = map_shape.linearize([x, z]) as usize;
+            let index = output.stride_to_index[stride] as usize;
+            output.positions[index][1] = config.vertical_scale * height_map.height(stride);
+            if let Some(lattice) = config.border_snap {
+                if x == imin[0] || x == imax[0] || z == imin[1] || z == imax[1] {
+                    output.positions[index] = snap_to_lattice(output.positions[index], lattice);
+                }
+            }
+        }
+    }
+
+    // Normals and curvature also change around them.
+    let reach = config.normal_mode.footprint();
+    for z in dirty_min[1].saturating_sub(reach).max(imin[1])..=(dirty_max[1] + reach).min(imax[1]) {
+        let spacing = match &config.geodetic {
+            Some(geodetic) => geodetic.meters_per_sample(z).map(|d| d as f32),
+            None => [1.0; 2],
+        };
+        for x in
+            dirty_min[0].saturating_sub(reach).max(imin[0])..=(dirty_max[0] + reach).min(imax[0])
+        {
+            let stride = map_shape.linearize([x, z]) as usize;
+            let index = output.stride_to_index[stride] as usize;
+            let normal =
+                vertex_normal(height_map, map_shape, [imin, imax], config, [x, z], spacing);
+            if config.packed_normals {
+                output.packed_normals[index] = normals::pack(normal);
+            } else {
+                output.normals[index] = normal;
+            }
+            if config.curvature {
+                output.curvature[index] = vertex_curvature(height_map, map_shape, config, [x, z]);
+            }
+        }
+    }
+
+    // These copy positions or depend on the slopes of whole triangles, and are cheap to regenerate.
+    if let Some(step) = config.shadow_caster_step {
+        output.shadow_caster.reset();
+        shadow_caster_mesh(map_shape, imin, imax, step, output);
+    }
+    if let Some(max_slope) = config.walkable_slope {
+        output.walkable_indices.clear();
+        output.steep_indices.clear();
+        partition_by_slope(max_slope, output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::height_mesh_with_config;
+    use ndshape::{ConstShape, ConstShape2u32};
+
+    #[test]
+    fn updated_region_matches_a_full_remesh() {
+        type MapShape = ConstShape2u32<20, 20>;
+        let mut heights: Vec<f32> = (0..MapShape::SIZE)
+            .map(|i| {
+                let [x, z] = <MapShape as ConstShape<u32, 2>>::delinearize(i);
+                (x as f32 * 0.4).sin() * 2.0 + (z as f32 * 0.3).cos()
+            })
+            .collect();
+        let config = HeightMeshConfig {
+            curvature: true,
+            walkable_slope: Some(0.5),
+            shadow_caster_step: Some(3),
+            content_hash: true,
+            ..Default::default()
+        };
+        let (min, max) = ([0; 2], [19; 2]);
+        let mut updated = HeightMeshBuffer::default();
+        height_mesh_with_config(&heights, &MapShape {}, min, max, &config, &mut updated);
+
+        // Dig a pit, including some samples on the boundary.
+        let (dirty_min, dirty_max) = ([0, 6], [8, 11]);
+        for z in dirty_min[1]..=dirty_max[1] {
+            for x in dirty_min[0]..=dirty_max[0] {
+                heights[<MapShape as ConstShape<u32, 2>>::linearize([x, z]) as usize] -= 3.0;
+            }
+        }
+        update_height_mesh_region(
+            &heights,
+            &MapShape {},
+            min,
+            max,
+            dirty_min,
+            dirty_max,
+            &config,
+            &mut updated,
+        );
+
+        let mut fresh = HeightMeshBuffer::default();
+        height_mesh_with_config(&heights, &MapShape {}, min, max, &config, &mut fresh);
+        assert_eq!(updated.positions, fresh.positions);
+        assert_eq!(updated.normals, fresh.normals);
+        assert_eq!(updated.curvature, fresh.curvature);
+        assert_eq!(updated.indices, fresh.indices);
+        assert_eq!(updated.walkable_indices, fresh.walkable_indices);
+        assert_eq!(updated.steep_indices, fresh.steep_indices);
+        assert_eq!(
+            updated.shadow_caster.positions,
+            fresh.shadow_caster.positions
+        );
+        assert_eq!(updated.shadow_caster.indices, fresh.shadow_caster.indices);
+        assert_eq!(updated.content_hash, fresh.content_hash);
+    }
+}
@@ -0,0 +1,113 @@
+/// How [`HeightMeshBuffer::uvs`](crate::HeightMeshBuffer::uvs) are generated. See
+/// [`HeightMeshConfig::uvs`](crate::HeightMeshConfig::uvs).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UvMode {
+    /// The interior of the mesh spans `[0, 1]` on both axes.
+    Normalized,
+    /// UVs are the `[x, z]` sample coordinates multiplied by `scale`, so a texture tiles seamlessly across chunks.
+    WorldSpace { scale: f32 },
+    /// Like [`UvMode::Normalized`], but mapped into one tile of a texture atlas, so chunked terrain can share a single
+    /// material.
+    ///
+    /// The atlas is divided into a grid of `tiles[0] * tiles[1]` equal tiles, and this chunk uses the tile at `tile`. Each
+    /// tile is shrunk by `inset` (a fraction of the tile size) on every side to keep filtering from bleeding into
+    /// neighboring tiles.
+    Atlas {
+        tile: [u32; 2],
+        tiles: [u32; 2],
+        inset: f32,
+    },
+}
+
+/// Writes UVs for a row-major grid of `grid_size` vertices, starting at sample coordinates `grid_min`.
+pub(crate) fn uvs(mode: &UvMode, grid_min: [u32; 2], grid_size: [u32; 2], uvs: &mut Vec<[f32; 2]>) {
+    let [w, h] = grid_size;
+    let cells_x = w.saturating_sub(1).max(1) as f32;
+    let cells_y = h.saturating_sub(1).max(1) as f32;
+    match *mode {
+        UvMode::Normalized => {
+            for z in 0..h {
+                for x in 0..w {
+                    uvs.push([x as f32 / cells_x, z as f32 / cells_y]);
+                }
+            }
+        }
+        UvMode::Atlas { tile, tiles, inset } => {
+            assert!(tile[0] < tiles[0] && tile[1] < tiles[1]);
+            let tile_size = [1.0 / tiles[0] as f32, 1.0 / tiles[1] as f32];
+            let span = 1.0 - 2.0 * inset;
+            for z in 0..h {
+                for x in 0..w {
+                    let u = inset + span * x as f32 / cells_x;
+                    let v = inset + span * z as f32 / cells_y;
+                    uvs.push([
+                        (tile[0] as f32 + u) * tile_size[0],
+                        (tile[1] as f32 + v) * tile_size[1],
+                    ]);
+                }
+            }
+        }
+        UvMode::WorldSpace { scale } => {
+            for z in 0..h {
+                for x in 0..w {
+                    uvs.push([
+                        (grid_min[0] + x) as f32 * scale,
+                        (grid_min[1] + z) as f32 * scale,
+                    ]);
+                }
+            }
+        }
+    }
+}
+
+/// Parameters for generating a lightmap UV set. See [`HeightMeshConfig::lightmap_uvs`](crate::HeightMeshConfig::lightmap_uvs).
+#[derive(Clone, Copy, Debug)]
+pub struct LightmapUvConfig {
+    /// The dimensions of the lightmap in texels.
+    pub resolution: [u32; 2],
+    /// The number of texels left empty between the chart and the edges of the lightmap.
+    ///
+    /// Bilinear sampling and dilation during baking read neighboring texels, so a padding of at least 1 (2 if the lightmap
+    /// will be mipmapped) avoids bleeding in whatever is packed next to this chart.
+    pub padding: u32,
+}
+
+impl Default for LightmapUvConfig {
+    fn default() -> Self {
+        Self {
+            resolution: [256; 2],
+            padding: 2,
+        }
+    }
+}
+
+/// Writes lightmap UVs for a row-major grid of `grid_size` vertices.
+///
+/// The grid is a single chart, since it never overlaps itself. The chart is stretched to fill the lightmap minus padding,
+/// and its edges are placed exactly on texel boundaries so that the padding is a whole number of texels on every side.
+pub(crate) fn lightmap_uvs(
+    config: &LightmapUvConfig,
+    grid_size: [u32; 2],
+    uvs: &mut Vec<[f32; 2]>,
+) {
+    let [res_x, res_y] = config.resolution;
+    assert!(
+        2 * config.padding < res_x && 2 * config.padding < res_y,
+        "Lightmap padding leaves no room for the chart"
+    );
+
+    let [w, h] = grid_size;
+    let pad = config.padding as f32;
+    let chart_x = (res_x - 2 * config.padding) as f32;
+    let chart_y = (res_y - 2 * config.padding) as f32;
+    let cells_x = w.saturating_sub(1).max(1) as f32;
+    let cells_y = h.saturating_sub(1).max(1) as f32;
+
+    for z in 0..h {
+        let v = (pad + chart_y * z as f32 / cells_y) / res_y as f32;
+        for x in 0..w {
+            let u = (pad + chart_x * x as f32 / cells_x) / res_x as f32;
+            uvs.push([u, v]);
+        }
+    }
+}
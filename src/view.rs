@@ -0,0 +1,422 @@
+/// A read-only source of height samples, indexed by the linear strides of the map's [`Shape`](ndshape::Shape).
+///
+/// The mesher and utilities accept any view, so height data can be read in place from other layouts, like one channel of a
+/// packed texture, rather than copied into a `[f32]` first.
+pub trait HeightMapView {
+    /// The number of samples in the view.
+    fn len(&self) -> usize;
+
+    /// The height of sample `i`.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `i >= self.len()`.
+    fn height(&self, i: usize) -> f32;
+
+    /// The height of sample `i`, without bounds checking.
+    ///
+    /// # Safety
+    ///
+    /// `i` must be less than `self.len()`.
+    #[inline]
+    unsafe fn height_unchecked(&self, i: usize) -> f32 {
+        self.height(i)
+    }
+
+    /// Returns true if the view has no samples.
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl HeightMapView for [f32] {
+    #[inline]
+    fn len(&self) -> usize {
+        <[f32]>::len(self)
+    }
+    #[inline]
+    fn height(&self, i: usize) -> f32 {
+        self[i]
+    }
+    #[inline]
+    unsafe fn height_unchecked(&self, i: usize) -> f32 {
+        *self.get_unchecked(i)
+    }
+}
+
+impl<const N: usize> HeightMapView for [f32; N] {
+    #[inline]
+    fn len(&self) -> usize {
+        N
+    }
+    #[inline]
+    fn height(&self, i: usize) -> f32 {
+        self[i]
+    }
+    #[inline]
+    unsafe fn height_unchecked(&self, i: usize) -> f32 {
+        *self.get_unchecked(i)
+    }
+}
+
+impl HeightMapView for Vec<f32> {
+    #[inline]
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+    #[inline]
+    fn height(&self, i: usize) -> f32 {
+        self[i]
+    }
+    #[inline]
+    unsafe fn height_unchecked(&self, i: usize) -> f32 {
+        *self.get_unchecked(i)
+    }
+}
+
+impl<V: HeightMapView + ?Sized> HeightMapView for &V {
+    #[inline]
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+    #[inline]
+    fn height(&self, i: usize) -> f32 {
+        (**self).height(i)
+    }
+    #[inline]
+    unsafe fn height_unchecked(&self, i: usize) -> f32 {
+        (**self).height_unchecked(i)
+    }
+}
+
+/// A scalar type that can be converted to a height. The conversion is numeric, not normalized, so e.g. `u16::MAX` becomes
+/// `65535.0`.
+pub trait Sample: Copy {
+    /// Converts the sample to a height.
+    fn to_height(self) -> f32;
+}
+
+macro_rules! impl_sample {
+    ($($t:ty),*) => {
+        $(
+            impl Sample for $t {
+                #[inline]
+                fn to_height(self) -> f32 {
+                    self as f32
+                }
+            }
+        )*
+    };
+}
+
+impl_sample!(u8, i8, u16, i16, u32, i32, f32, f64);
+
+/// A view of one channel of interleaved multi-channel samples, like the R channel of RGBA16 texture data.
+///
+/// Sample `i` is read from `data[i * stride + channel]`.
+#[derive(Clone, Copy, Debug)]
+pub struct Channel<'a, T> {
+    data: &'a [T],
+    stride: usize,
+    channel: usize,
+}
+
+impl<'a, T: Sample> Channel<'a, T> {
+    /// Selects `channel` from `data` with `stride` elements per sample (e.g. 4 for RGBA).
+    pub fn new(data: &'a [T], stride: usize, channel: usize) -> Self {
+        assert!(
+            channel < stride,
+            "Channel {} out of range for stride {}",
+            channel,
+            stride
+        );
+        Self {
+            data,
+            stride,
+            channel,
+        }
+    }
+}
+
+impl<T: Sample> HeightMapView for Channel<'_, T> {
+    #[inline]
+    fn len(&self) -> usize {
+        // A partial trailing sample still holds this channel if it's long enough.
+        (self.data.len() + self.stride - 1 - self.channel) / self.stride
+    }
+    #[inline]
+    fn height(&self, i: usize) -> f32 {
+        self.data[i * self.stride + self.channel].to_height()
+    }
+    #[inline]
+    unsafe fn height_unchecked(&self, i: usize) -> f32 {
+        self.data
+            .get_unchecked(i * self.stride + self.channel)
+            .to_height()
+    }
+}
+
+/// Lazy element-wise operations on any [`HeightMapView`]. Each returns a new view that computes its heights on demand, so
+/// simple adjustments don't require allocating a modified copy of a large map.
+///
+/// ```
+/// use height_mesh::{HeightMapView, HeightMapViewExt};
+///
+/// let base = vec![0.0, 1.0, 2.0, 3.0];
+/// let floor = vec![1.5; 4];
+/// let view = base.scale(2.0).offset(-1.0).max_with(&floor).clamp(0.0, 4.0);
+/// assert_eq!(view.height(0), 1.5);
+/// assert_eq!(view.height(3), 4.0);
+/// ```
+pub trait HeightMapViewExt: HeightMapView + Sized {
+    /// Multiplies every height by `factor`.
+    fn scale(self, factor: f32) -> Scale<Self> {
+        Scale { view: self, factor }
+    }
+
+    /// Adds `offset` to every height.
+    fn offset(self, offset: f32) -> Offset<Self> {
+        Offset { view: self, offset }
+    }
+
+    /// Clamps every height to `[min, max]`. Panics unless `min <= max`.
+    fn clamp(self, min: f32, max: f32) -> Clamp<Self> {
+        assert!(min <= max, "clamp range must satisfy min <= max");
+        Clamp {
+            view: self,
+            min,
+            max,
+        }
+    }
+
+    /// Clamps and linearly remaps every height, as described by `remap`.
+    fn remap(self, remap: HeightRemap) -> Remap<Self> {
+        Remap { view: self, remap }
+    }
+
+    /// Reads a map of `dims` logical samples that is stored in a different order, so it can be meshed with a row-major
+    /// shape of `dims` without first copying it into row-major order.
+    fn with_layout(self, dims: [u32; 2], layout: StorageLayout) -> Relayout<Self> {
+        assert_eq!(self.len(), dims[0] as usize * dims[1] as usize);
+        Relayout {
+            view: self,
+            dims,
+            layout,
+        }
+    }
+
+    /// Takes the element-wise minimum with `other`, which must have the same length.
+    fn min_with<O: HeightMapView>(self, other: O) -> MinWith<Self, O> {
+        assert_eq!(self.len(), other.len());
+        MinWith { view: self, other }
+    }
+
+    /// Takes the element-wise maximum with `other`, which must have the same length.
+    fn max_with<O: HeightMapView>(self, other: O) -> MaxWith<Self, O> {
+        assert_eq!(self.len(), other.len());
+        MaxWith { view: self, other }
+    }
+}
+
+impl<V: HeightMapView> HeightMapViewExt for V {}
+
+macro_rules! impl_unary_view {
+    ($name:ident { $($field:ident: $t:ty),* }, |$self:ident, $h:ident| $op:expr) => {
+        /// See [`HeightMapViewExt`].
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name<V> {
+            view: V,
+            $($field: $t),*
+        }
+
+        impl<V: HeightMapView> HeightMapView for $name<V> {
+            #[inline]
+            fn len(&self) -> usize {
+                self.view.len()
+            }
+            #[inline]
+            fn height(&$self, i: usize) -> f32 {
+                let $h = $self.view.height(i);
+                $op
+            }
+            #[inline]
+            unsafe fn height_unchecked(&$self, i: usize) -> f32 {
+                let $h = $self.view.height_unchecked(i);
+                $op
+            }
+        }
+    };
+}
+
+impl_unary_view!(Scale { factor: f32 }, |self, h| h * self.factor);
+impl_unary_view!(Offset { offset: f32 }, |self, h| h + self.offset);
+impl_unary_view!(Clamp { min: f32, max: f32 }, |self, h| h
+    .clamp(self.min, self.max));
+impl_unary_view!(Remap { remap: HeightRemap }, |self, h| self.remap.apply(h));
+
+/// Clamps heights to the `input` range, then maps that range linearly onto the `output` range, for out-of-range or
+/// differently scaled sensor data. See [`HeightMapViewExt::remap`] and
+/// [`HeightMeshConfig::height_remap`](crate::HeightMeshConfig::height_remap).
+///
+/// Either range may be reversed (with `min > max`), which inverts the heights.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeightRemap {
+    /// The `[min, max]` range of valid input heights.
+    pub input: [f32; 2],
+    /// The `[min, max]` range that the input range is mapped to.
+    pub output: [f32; 2],
+}
+
+impl HeightRemap {
+    /// Only clamps heights to `[min, max]`, without remapping them.
+    pub fn clamp(min: f32, max: f32) -> Self {
+        Self {
+            input: [min, max],
+            output: [min, max],
+        }
+    }
+
+    /// Applies the remap to one height.
+    #[inline]
+    pub fn apply(&self, h: f32) -> f32 {
+        let [in_min, in_max] = self.input;
+        let [out_min, out_max] = self.output;
+        let h = h.clamp(in_min.min(in_max), in_min.max(in_max));
+        if in_max != in_min {
+            out_min + (h - in_min) * (out_max - out_min) / (in_max - in_min)
+        } else {
+            out_min
+        }
+    }
+}
+
+macro_rules! impl_binary_view {
+    ($name:ident, $op:ident) => {
+        /// See [`HeightMapViewExt`].
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name<V, O> {
+            view: V,
+            other: O,
+        }
+
+        impl<V: HeightMapView, O: HeightMapView> HeightMapView for $name<V, O> {
+            #[inline]
+            fn len(&self) -> usize {
+                self.view.len()
+            }
+            #[inline]
+            fn height(&self, i: usize) -> f32 {
+                self.view.height(i).$op(self.other.height(i))
+            }
+            #[inline]
+            unsafe fn height_unchecked(&self, i: usize) -> f32 {
+                self.view
+                    .height_unchecked(i)
+                    .$op(self.other.height_unchecked(i))
+            }
+        }
+    };
+}
+
+impl_binary_view!(MinWith, min);
+impl_binary_view!(MaxWith, max);
+
+/// How the samples of a map are ordered in memory, for [`HeightMapViewExt::with_layout`]. The default is the row-major,
+/// X-fastest order that the mesher expects.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StorageLayout {
+    /// Samples are stored column by column (Y varies fastest), as in Fortran, MATLAB, and many GIS rasters.
+    pub column_major: bool,
+    /// The first stored row is the last row of the map, as in most image formats, where row 0 is the top of the image.
+    pub flip_y: bool,
+    /// The first stored column is the last column of the map.
+    pub flip_x: bool,
+}
+
+/// See [`HeightMapViewExt::with_layout`].
+#[derive(Clone, Copy, Debug)]
+pub struct Relayout<V> {
+    view: V,
+    dims: [u32; 2],
+    layout: StorageLayout,
+}
+
+impl<V> Relayout<V> {
+    /// The storage index of the row-major index `i`.
+    #[inline]
+    fn storage_index(&self, i: usize) -> usize {
+        let [w, h] = self.dims.map(|d| d as usize);
+        let (mut x, mut y) = (i % w, i / w);
+        if self.layout.flip_x {
+            x = w - 1 - x;
+        }
+        if self.layout.flip_y {
+            y = h - 1 - y;
+        }
+        if self.layout.column_major {
+            x * h + y
+        } else {
+            y * w + x
+        }
+    }
+}
+
+impl<V: HeightMapView> HeightMapView for Relayout<V> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.view.len()
+    }
+    #[inline]
+    fn height(&self, i: usize) -> f32 {
+        self.view.height(self.storage_index(i))
+    }
+    #[inline]
+    unsafe fn height_unchecked(&self, i: usize) -> f32 {
+        self.view.height_unchecked(self.storage_index(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remap_reversed_range_inverts() {
+        let remap = HeightRemap {
+            input: [10.0, 0.0],
+            output: [0.0, 1.0],
+        };
+        assert_eq!(remap.apply(10.0), 0.0);
+        assert_eq!(remap.apply(0.0), 1.0);
+        assert_eq!(remap.apply(2.5), 0.75);
+        // Out-of-range heights are clamped to the input range either way.
+        assert_eq!(remap.apply(-5.0), 1.0);
+        assert_eq!(remap.apply(20.0), 0.0);
+    }
+
+    #[test]
+    fn remap_degenerate_range() {
+        let remap = HeightRemap {
+            input: [3.0, 3.0],
+            output: [1.0, 2.0],
+        };
+        assert_eq!(remap.apply(-1.0), 1.0);
+        assert_eq!(remap.apply(7.0), 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "min <= max")]
+    fn clamp_rejects_reversed_range() {
+        let heights = [0.0f32; 4];
+        let _ = (&heights[..]).clamp(1.0, 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "left == right")]
+    fn with_layout_checks_len_without_overflow() {
+        // 65536 * 65536 wraps to 0 in u32, which would match the empty slice.
+        let heights: [f32; 0] = [];
+        let _ = (&heights[..]).with_layout([65536, 65536], StorageLayout::default());
+    }
+}
@@ -0,0 +1,196 @@
+use crate::HeightMapView;
+
+use ndshape::Shape;
+
+/// The result of a cut-and-fill computation, in cubic sample units (horizontal spacing of 1, times height units).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CutFill {
+    /// The volume of material above the reference surface, which would have to be removed to reach it.
+    pub cut: f64,
+    /// The volume of empty space below the reference surface, which would have to be filled to reach it.
+    pub fill: f64,
+}
+
+impl CutFill {
+    /// `cut - fill`: positive when there is a surplus of material.
+    pub fn net(&self) -> f64 {
+        self.cut - self.fill
+    }
+}
+
+/// The area of the surface generated by [`height_mesh`](crate::height_mesh) for the same region, in square sample units.
+pub fn surface_area<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    height_map: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+) -> f64 {
+    let mut area = 0.0;
+    for_each_triangle(min, max, |[a, b, c]| {
+        let h = |p: [u32; 2]| height_map.height(map_shape.linearize(p) as usize) as f64;
+        let [pa, pb, pc] = [a, b, c].map(|p| [p[0] as f64, h(p), p[1] as f64]);
+        let u = [pb[0] - pa[0], pb[1] - pa[1], pb[2] - pa[2]];
+        let v = [pc[0] - pa[0], pc[1] - pa[1], pc[2] - pa[2]];
+        let n = [
+            u[1] * v[2] - u[2] * v[1],
+            u[2] * v[0] - u[0] * v[2],
+            u[0] * v[1] - u[1] * v[0],
+        ];
+        area += 0.5 * (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    });
+    area
+}
+
+/// The volume between the meshed surface of `existing` and a horizontal plane at `plane_height`, for the same region
+/// and triangulation as [`height_mesh`](crate::height_mesh).
+pub fn cut_fill_to_plane<M: HeightMapView + ?Sized, S: Shape<u32, 2>>(
+    existing: &M,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+    plane_height: f32,
+) -> CutFill {
+    cut_fill_by(min, max, |p| {
+        existing.height(map_shape.linearize(p) as usize) as f64 - plane_height as f64
+    })
+}
+
+/// The volume between the meshed surfaces of `existing` and `design`, which must have the same shape, for the same region
+/// and triangulation as [`height_mesh`](crate::height_mesh).
+///
+/// Cut is where `existing` lies above `design`, and fill is where it lies below. This also measures the effect of a
+/// terrain edit, with the map before the edit as `design`: cut is then the volume added and fill the volume removed.
+pub fn cut_fill<E, D, S>(
+    existing: &E,
+    design: &D,
+    map_shape: &S,
+    min: [u32; 2],
+    max: [u32; 2],
+) -> CutFill
+where
+    E: HeightMapView + ?Sized,
+    D: HeightMapView + ?Sized,
+    S: Shape<u32, 2>,
+{
+    assert_eq!(existing.len(), design.len());
+
+    cut_fill_by(min, max, |p| {
+        let stride = map_shape.linearize(p) as usize;
+        existing.height(stride) as f64 - design.height(stride) as f64
+    })
+}
+
+fn cut_fill_by(min: [u32; 2], max: [u32; 2], difference: impl Fn([u32; 2]) -> f64) -> CutFill {
+    let mut result = CutFill::default();
+    for_each_triangle(min, max, |corners| {
+        let d = corners.map(&difference);
+        // Each triangle covers half of a unit cell.
+        let (above, below) = split_prism_volume(0.5, d);
+        result.cut += above;
+        result.fill += below;
+    });
+    result
+}
+
+/// Calls `f` with the sample coordinates of each triangle generated by [`height_mesh`](crate::height_mesh).
+fn for_each_triangle(min: [u32; 2], max: [u32; 2], mut f: impl FnMut([[u32; 2]; 3])) {
+    // Quads have their bottom-left corner in [min + 1, max - 2].
+    for z in min[1] + 1..max[1].saturating_sub(1) {
+        for x in min[0] + 1..max[0].saturating_sub(1) {
+            let bl = [x, z];
+            let br = [x + 1, z];
+            let tl = [x, z + 1];
+            let tr = [x + 1, z + 1];
+            f([bl, tl, tr]);
+            f([bl, tr, br]);
+        }
+    }
+}
+
+/// The volumes above and below zero of a linear function over a triangle with projected `area`, given its values `d` at
+/// the corners.
+fn split_prism_volume(area: f64, mut d: [f64; 3]) -> (f64, f64) {
+    let total = area * (d[0] + d[1] + d[2]) / 3.0;
+    if d.iter().all(|&v| v >= 0.0) {
+        return (total, 0.0);
+    }
+    if d.iter().all(|&v| v <= 0.0) {
+        return (0.0, -total);
+    }
+
+    // Exactly one corner has a different sign from the other two. The part of the triangle on that corner's side of the
+    // zero line is a tetrahedron with volume area * p^3 / (3 * (p - q) * (p - r)).
+    d.sort_by(|a, b| a.total_cmp(b));
+    let (lone, others) = if d[1] >= 0.0 {
+        (d[0], [d[1], d[2]])
+    } else {
+        (d[2], [d[0], d[1]])
+    };
+    let wedge = area * lone.powi(3) / (3.0 * (lone - others[0]) * (lone - others[1]));
+    if lone > 0.0 {
+        (wedge, wedge - total)
+    } else {
+        // Here, wedge is negative.
+        (total - wedge, -wedge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ndshape::Shape2u32;
+
+    fn assert_close((above, below): (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (above - expected.0).abs() < 1e-12 && (below - expected.1).abs() < 1e-12,
+            "got {:?}, expected {:?}",
+            (above, below),
+            expected
+        );
+    }
+
+    #[test]
+    fn prism_fully_above() {
+        // The volume is the area times the mean height.
+        assert_close(split_prism_volume(0.5, [1.0, 2.0, 3.0]), (1.0, 0.0));
+        assert_close(split_prism_volume(0.5, [0.0, 0.0, 3.0]), (0.5, 0.0));
+    }
+
+    #[test]
+    fn prism_fully_below() {
+        assert_close(split_prism_volume(0.5, [-1.0, -2.0, -3.0]), (0.0, 1.0));
+        assert_close(split_prism_volume(0.5, [0.0, -3.0, 0.0]), (0.0, 0.5));
+    }
+
+    #[test]
+    fn prism_crossing_zero() {
+        // The part on the lone corner's side is a triangle scaled by p / (p - q) and p / (p - r) along its two edges, so
+        // the tetrahedron over it has volume area * (p / (p - q)) * (p / (p - r)) * |p| / 3.
+        let area = 0.5;
+        let wedge = |p: f64, q: f64, r: f64| area * (p / (p - q)) * (p / (p - r)) * p.abs() / 3.0;
+        let total = |d: [f64; 3]| area * (d[0] + d[1] + d[2]) / 3.0;
+
+        let d = [1.0, -1.0, -1.0];
+        let above = wedge(1.0, -1.0, -1.0);
+        assert_close(split_prism_volume(area, d), (above, above - total(d)));
+
+        let d = [-1.0, 3.0, -2.0];
+        let above = wedge(3.0, -1.0, -2.0);
+        assert_close(split_prism_volume(area, d), (above, above - total(d)));
+
+        let d = [1.0, -2.0, 1.0];
+        let below = wedge(-2.0, 1.0, 1.0);
+        assert_close(split_prism_volume(area, d), (below + total(d), below));
+    }
+
+    #[test]
+    fn tilted_plane_crossing_reference() {
+        // h = x - 2.5 over the cells from x = 1 to 4 and z = 1 to 4, so the cut and fill are both a 1.5 by 1.5 by 3 wedge.
+        let shape = Shape2u32::new([6, 6]);
+        let heights: Vec<f32> = (0..36).map(|i| (i % 6) as f32).collect();
+        let result = cut_fill_to_plane(&heights[..], &shape, [0; 2], [5; 2], 2.5);
+        assert_close((result.cut, result.fill), (3.375, 3.375));
+        assert_eq!(result.net(), 0.0);
+    }
+}